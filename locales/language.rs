@@ -1,5 +1,7 @@
+#[cfg(windows)]
 use windows::Win32::Globalization::GetSystemDefaultLCID;
 
+#[cfg(windows)]
 pub fn set_locale() {
     let sys_lcid = unsafe { GetSystemDefaultLCID() };
 
@@ -15,3 +17,17 @@ pub fn set_locale() {
         _ => rust_i18n::set_locale("en"),
     };
 }
+
+/// `LC_ALL`, `LC_MESSAGES` or `LANG`, e.g. `zh_CN.UTF-8`
+#[cfg(not(windows))]
+pub fn set_locale() {
+    let lang = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_default();
+
+    match lang.starts_with("zh") {
+        true => rust_i18n::set_locale("zh-CN"),
+        false => rust_i18n::set_locale("en"),
+    };
+}
@@ -0,0 +1,201 @@
+use super::{
+    tabs::Tab,
+    tools::{CustomizeIcon, export_customize_icon},
+};
+use crate::{
+    link::{
+        create::{NewShortcut, create_shortcut},
+        list::LinkList,
+    },
+    utils::{ensure_local_app_folder_exists, notify},
+};
+
+use std::ffi::OsStr;
+
+use dioxus::prelude::*;
+use log::*;
+use rfd::FileDialog;
+use rust_i18n::t;
+
+#[component]
+pub fn create(
+    mut show_create: Signal<bool>,
+    link_list: Signal<LinkList>,
+    mut current_tab: Signal<Tab>,
+    customize_icon: Signal<CustomizeIcon>,
+) -> Element {
+    let mut new_shortcut = use_signal(NewShortcut::default);
+
+    if !*show_create.read() {
+        return rsx!();
+    }
+
+    let new_shortcut_read = new_shortcut.read().clone();
+    let has_customize_icon = customize_icon.read().link.is_some();
+
+    rsx! {
+        style { {include_str!("css/create.css")} }
+        div { class: "create-container",
+            div {
+                class: "create-modal",
+                onmousedown: |event| event.stop_propagation(), // 屏蔽拖拽
+                div { class: "head",
+                    span { {t!("TOOL_CREATE_SHORTCUT")} }
+                    button { onclick: move |_| *show_create.write() = false, "X" }
+                }
+                // 目标
+                div { class: "field",
+                    label { {t!("CREATE_TARGET")} }
+                    input {
+                        r#type: "text",
+                        autocomplete: "off",
+                        placeholder: "C:\\Program Files\\... / https://...",
+                        value: new_shortcut_read.target_path.clone(),
+                        oninput: move |event| new_shortcut.write().target_path = event.value(),
+                    }
+                    button {
+                        onclick: move |_| {
+                            if let Some(path) = FileDialog::new()
+                                .set_title(t!("CREATE_TARGET"))
+                                .pick_file()
+                            {
+                                let mut new_shortcut = new_shortcut.write();
+                                // 默认使用目标文件名与所在目录
+                                if new_shortcut.name.is_empty() {
+                                    new_shortcut.name = path
+                                        .file_stem()
+                                        .and_then(OsStr::to_str)
+                                        .unwrap_or_default()
+                                        .to_owned();
+                                }
+                                if new_shortcut.working_dir.is_empty() {
+                                    new_shortcut.working_dir = path
+                                        .parent()
+                                        .map(|p| p.to_string_lossy().into_owned())
+                                        .unwrap_or_default();
+                                }
+                                new_shortcut.target_path = path.to_string_lossy().into_owned();
+                            }
+                        },
+                        {t!("BROWSE")}
+                    }
+                }
+                // 名称
+                div { class: "field",
+                    label { {t!("CREATE_NAME")} }
+                    input {
+                        r#type: "text",
+                        autocomplete: "off",
+                        value: new_shortcut_read.name.clone(),
+                        oninput: move |event| new_shortcut.write().name = event.value(),
+                    }
+                }
+                // 参数
+                div { class: "field",
+                    label { {t!("ARGUMENTS")} }
+                    input {
+                        r#type: "text",
+                        autocomplete: "off",
+                        value: new_shortcut_read.arguments.clone(),
+                        oninput: move |event| new_shortcut.write().arguments = event.value(),
+                    }
+                }
+                // 起始位置
+                div { class: "field",
+                    label { {t!("CREATE_WORKING_DIR")} }
+                    input {
+                        r#type: "text",
+                        autocomplete: "off",
+                        value: new_shortcut_read.working_dir.clone(),
+                        oninput: move |event| new_shortcut.write().working_dir = event.value(),
+                    }
+                    button {
+                        onclick: move |_| {
+                            if let Some(path) = FileDialog::new()
+                                .set_title(t!("CREATE_WORKING_DIR"))
+                                .pick_folder()
+                            {
+                                new_shortcut.write().working_dir = path.to_string_lossy().into_owned();
+                            }
+                        },
+                        {t!("BROWSE")}
+                    }
+                }
+                // 图标
+                div { class: "field",
+                    label { {t!("CREATE_ICON")} }
+                    input {
+                        r#type: "text",
+                        autocomplete: "off",
+                        placeholder: t!("CREATE_ICON_FROM_TARGET").into_owned(),
+                        value: new_shortcut_read.icon_path.clone(),
+                        oninput: move |event| new_shortcut.write().icon_path = event.value(),
+                    }
+                    button {
+                        onclick: move |_| {
+                            let mut dialog = FileDialog::new()
+                                .set_title(t!("SELECT_ICON_FILE"))
+                                .add_filter(
+                                    "ICON",
+                                    &["ico", "png", "svg", "bmp", "webp", "tiff", "exe", "dll"],
+                                );
+                            // 默认打开自定义图标目录
+                            if let Ok(local_path) = ensure_local_app_folder_exists() {
+                                dialog = dialog.set_directory(local_path.join("icons"));
+                            }
+                            if let Some(path) = dialog.pick_file() {
+                                new_shortcut.write().icon_path = path.to_string_lossy().into_owned();
+                            }
+                        },
+                        {t!("BROWSE")}
+                    }
+                    // 使用工具页正在自定义的图标
+                    button {
+                        display: if has_customize_icon { "inline-block" } else { "none" },
+                        onclick: move |_| {
+                            match export_customize_icon(&customize_icon.read()) {
+                                Ok(icon_path) => new_shortcut.write().icon_path = icon_path,
+                                Err(e) => {
+                                    error!("{e}");
+                                    notify(&format!("{e}"));
+                                }
+                            }
+                        },
+                        {t!("CREATE_USE_CUSTOMIZE_ICON")}
+                    }
+                }
+                button {
+                    class: "confirm",
+                    onclick: move |_| {
+                        let new_shortcut_read = new_shortcut.read().clone();
+                        let new_shortcut_read = NewShortcut {
+                            target_path: new_shortcut_read.target_path.trim().to_owned(),
+                            working_dir: new_shortcut_read.working_dir.trim().to_owned(),
+                            icon_path: new_shortcut_read.icon_path.trim().to_owned(),
+                            ..new_shortcut_read
+                        };
+                        match create_shortcut(link_list, &new_shortcut_read) {
+                            Ok(name) => {
+                                notify(&format!("{name}: {}", t!("SUCCESS_CREATE_SHORTCUT")));
+                                new_shortcut.set(NewShortcut::default());
+                                *show_create.write() = false;
+                                *current_tab.write() = Tab::Home;
+                            }
+                            Err(e) => {
+                                error!("{}: {e}", t!("ERROR_CREATE_SHORTCUT"));
+                                notify(&format!("{}: {e}", t!("ERROR_CREATE_SHORTCUT")));
+                            }
+                        }
+                    },
+                    {t!("CONFIRM")}
+                }
+                span { class: "hint",
+                    {
+                        let dir = link_list.read().source.name();
+                        format!("{}: {dir}", t!("CREATE_SAVE_TO"))
+                    }
+                }
+            }
+        }
+    }
+}
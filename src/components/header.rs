@@ -3,7 +3,7 @@ use super::{
     tabs::Tab,
 };
 use crate::{
-    link::{list::LinkList, modify::change_all_shortcuts_icons},
+    link::{list::LinkList, modify::plan_all_shortcuts_icons, plan::MatchPlan},
     utils::notify,
 };
 
@@ -29,6 +29,7 @@ pub fn header(
     mut filter_name: Signal<Option<String>>,
     mut current_tab: Signal<Tab>,
     mut show_msgbox: Signal<Option<Msgbox>>,
+    mut show_plan: Signal<Option<MatchPlan>>,
 ) -> Element {
     rsx! {
         style { {include_str!("css/header.css")} }
@@ -69,14 +70,10 @@ pub fn header(
                     class: "change",
                     onmousedown: |event| event.stop_propagation(),
                     onclick: move |_| {
-                        match change_all_shortcuts_icons(link_list) {
-                            Ok(true) => {
-                                notify(&t!("SUCCESS_CHANGE_ALL"));
-                                if *current_tab.read() != Tab::Home {
-                                    *current_tab.write() = Tab::Home;
-                                }
-                            }
-                            Ok(false) => println!("{}", t!("NOT_CHANGE_ALL")),
+                        // 先预览匹配计划，确认后再更换
+                        match plan_all_shortcuts_icons(link_list) {
+                            Ok(Some(plan)) => *show_plan.write() = Some(plan),
+                            Ok(None) => println!("{}", t!("NOT_CHANGE_ALL")),
                             Err(e) => {
                                 log::error!("{e}");
                                 notify(&t!("ERROR_CHANGE_ALL"));
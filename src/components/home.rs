@@ -1,13 +1,15 @@
 use super::{
+    icon_picker::{IconPicker, IconTarget, open_icon_picker},
     msgbox::{Action, MsgIcon, Msgbox},
     tabs::Tab,
     tools::CustomizeIcon,
 };
 use crate::{
-    image::base64::get_img_base64_by_path,
+    image::{base64::get_icon_base64_by_location, pe_icon::is_pe_icon_file},
     link::{
+        journal::{JournalScope, redo_icon_change, undo_icon_change},
         list::{LinkList, LinkProp},
-        modify::change_single_shortcut_icon,
+        modify::{change_single_shortcut_icon, pick_single_shortcut_icon},
     },
     utils::notify,
 };
@@ -32,6 +34,7 @@ pub fn home(
     mut customize_icon: Signal<CustomizeIcon>,
     mut show_msgbox: Signal<Option<Msgbox>>,
     mut show_prop: Signal<bool>,
+    show_icon_picker: Signal<Option<IconPicker>>,
 ) -> Element {
     let filter_link_list_items = match filter_name.read().as_deref() {
         Some(name) => link_list
@@ -60,9 +63,19 @@ pub fn home(
             onmousedown: |event| event.stop_propagation(), // 屏蔽拖拽
             for (filter_index , (item , index)) in filter_link_list_items.into_iter().enumerate() {
                 if let Some(index) = index {
-                    icon_button { item, index, link_list }
+                    icon_button {
+                        item,
+                        index,
+                        link_list,
+                        show_icon_picker,
+                    }
                 } else {
-                    icon_button { item, index: filter_index, link_list }
+                    icon_button {
+                        item,
+                        index: filter_index,
+                        link_list,
+                        show_icon_picker,
+                    }
                 }
             }
         }
@@ -73,23 +86,23 @@ pub fn home(
                 customize_icon,
                 show_msgbox,
                 show_prop,
+                show_icon_picker,
             }
         }
     }
 }
 
 #[component]
-pub fn icon_button(item: LinkProp, index: usize, mut link_list: Signal<LinkList>) -> Element {
+pub fn icon_button(
+    item: LinkProp,
+    index: usize,
+    mut link_list: Signal<LinkList>,
+    show_icon_picker: Signal<Option<IconPicker>>,
+) -> Element {
     rsx! {
         button {
             class: "icon-button",
-            ondoubleclick: move |_| {
-                match change_single_shortcut_icon(link_list) {
-                    Ok(Some(name)) => notify(&format!("{}: {}", t!("SUCCESS_CHANGE_ONE"), name)),
-                    Err(e) => error!("Failed to change the shortcut icon - {e}"),
-                    _ => {}
-                };
-            },
+            ondoubleclick: move |_| change_icon(link_list, show_icon_picker),
             onclick: move |_| link_list.write().state.select = Some(index),
             div { class: "img-container",
                 img { src: item.icon_base64.clone() }
@@ -106,6 +119,7 @@ pub fn icon_modify(
     mut customize_icon: Signal<CustomizeIcon>,
     mut show_msgbox: Signal<Option<Msgbox>>,
     mut show_prop: Signal<bool>,
+    show_icon_picker: Signal<Option<IconPicker>>,
 ) -> Element {
     if let Some(index) = link_list.read().state.select {
         let link_target_path = &link_list.read().items[index].target_path;
@@ -157,7 +171,10 @@ pub fn icon_modify(
                         onclick: move |_| {
                             let mut link_prop = link_list.read().items[index].clone();
                             if Path::new(&link_prop.icon_path).exists() {
-                                link_prop.icon_base64 = get_img_base64_by_path(&link_prop.icon_path);
+                                link_prop.icon_base64 = get_icon_base64_by_location(
+                                    &link_prop.icon_path,
+                                    link_prop.icon_index.parse().unwrap_or(0),
+                                );
                             }
                             *customize_icon.write() = CustomizeIcon {
                                 link: Some(link_prop),
@@ -170,13 +187,7 @@ pub fn icon_modify(
                     button {
                         class: "allowed",
                         onmousedown: |event| event.stop_propagation(),
-                        onclick: move |_| {
-                            match change_single_shortcut_icon(link_list) {
-                                Ok(Some(name)) => notify(&format!("{}: {name}", t!("SUCCESS_CHANGE_ONE"))),
-                                Err(e) => error!("Failed to change the shortcut icon - {e}"),
-                                _ => {}
-                            };
-                        },
+                        onclick: move |_| change_icon(link_list, show_icon_picker),
                         span { {t!("CHANGE_ONE")} }
                     }
                     button {
@@ -193,15 +204,45 @@ pub fn icon_modify(
                         span { {t!("RESTORE_ONE")} }
                     }
                     button {
-                        class: should_open_target_dir_allow,
+                        class: "allowed",
                         onmousedown: |event| event.stop_propagation(),
                         onclick: move |_| {
-                            if should_open_target_dir_allow == "allowed" {
-                                if let Err(e) = opener::open(&link_target_dir) {
-                                    error!("Failed to open {link_target_dir}: {e}");
+                            match undo_icon_change(link_list, JournalScope::Shortcut) {
+                                Ok(0) => notify(&t!("NOTHING_TO_UNDO")),
+                                Ok(_) => notify(&t!("SUCCESS_UNDO")),
+                                Err(e) => {
+                                    error!("{e}");
+                                    notify(&format!("{}: {e}", t!("ERROR_UNDO_REDO")));
                                 }
                             }
                         },
+                        span { {t!("UNDO_ONE")} }
+                    }
+                    button {
+                        class: "allowed",
+                        onmousedown: |event| event.stop_propagation(),
+                        onclick: move |_| {
+                            match redo_icon_change(link_list, JournalScope::Shortcut) {
+                                Ok(0) => notify(&t!("NOTHING_TO_REDO")),
+                                Ok(_) => notify(&t!("SUCCESS_REDO")),
+                                Err(e) => {
+                                    error!("{e}");
+                                    notify(&format!("{}: {e}", t!("ERROR_UNDO_REDO")));
+                                }
+                            }
+                        },
+                        span { {t!("REDO_ONE")} }
+                    }
+                    button {
+                        class: should_open_target_dir_allow,
+                        onmousedown: |event| event.stop_propagation(),
+                        onclick: move |_| {
+                            if should_open_target_dir_allow == "allowed"
+                                && let Err(e) = opener::open(&link_target_dir)
+                            {
+                                error!("Failed to open {link_target_dir}: {e}");
+                            }
+                        },
                         span { {t!("TARGET_DIR")} }
                     }
                     button {
@@ -210,10 +251,10 @@ pub fn icon_modify(
                         onclick: move |_| {
                             if should_open_icon_dir_allow == "allowed" {
                                 let link_icon_dir_path = Path::new(&link_icon_path).parent();
-                                if let Some(path) = link_icon_dir_path {
-                                    if let Err(e) = opener::open(path) {
-                                        error!("Failed to open {path:?}: {e}");
-                                    }
+                                if let Some(path) = link_icon_dir_path
+                                    && let Err(e) = opener::open(path)
+                                {
+                                    error!("Failed to open {path:?}: {e}");
                                 }
                             }
                         },
@@ -232,3 +273,19 @@ pub fn icon_modify(
         rsx!()
     }
 }
+
+/// Pick a new icon for the selected shortcut, a program or library opens the icon picker
+fn change_icon(link_list: Signal<LinkList>, show_icon_picker: Signal<Option<IconPicker>>) {
+    let Some(icon_path) = pick_single_shortcut_icon() else {
+        return;
+    };
+    if is_pe_icon_file(&icon_path) {
+        open_icon_picker(show_icon_picker, &icon_path, IconTarget::Shortcut);
+        return;
+    }
+    match change_single_shortcut_icon(link_list, &icon_path, 0) {
+        Ok(Some(name)) => notify(&format!("{}: {name}", t!("SUCCESS_CHANGE_ONE"))),
+        Err(e) => error!("Failed to change the shortcut icon - {e}"),
+        _ => {}
+    };
+}
@@ -0,0 +1,193 @@
+use super::tools::CustomizeIcon;
+use crate::{
+    image::{
+        base64::get_ico_base64,
+        pe_icon::{IconResourceId, pe_icon_groups},
+    },
+    link::{
+        list::{LinkList, LinkProp},
+        modify::change_single_shortcut_icon,
+    },
+    utils::notify,
+};
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, ensure};
+use dioxus::prelude::*;
+use log::*;
+use rust_i18n::t;
+
+/// What the picked icon is used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IconTarget {
+    /// The selected shortcut of the home list
+    Shortcut,
+    /// The icon of the Tools panel, `keep_link` keeps the shortcut being customized
+    Customize { keep_link: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickerIcon {
+    pub index: usize,
+    pub id: IconResourceId,
+    pub sizes: Vec<u32>,
+    pub base64: String,
+}
+
+impl PickerIcon {
+    /// Icon index written into the shortcut, a resource id is written negated
+    fn icon_index(&self, by_id: bool) -> i32 {
+        match (&self.id, by_id) {
+            (IconResourceId::Id(id), true) => -(*id as i32),
+            _ => self.index as i32,
+        }
+    }
+}
+
+/// Icon groups of a program or library to choose from - 图标选择
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconPicker {
+    pub pe_path: PathBuf,
+    pub target: IconTarget,
+    pub icons: Vec<PickerIcon>,
+    /// Refer to the icon by resource id, it stays the same when icons are added to the file
+    pub by_id: bool,
+}
+
+impl IconPicker {
+    pub fn new(pe_path: &Path, target: IconTarget) -> Result<Self> {
+        let icons = pe_icon_groups(pe_path)?
+            .into_iter()
+            .map(|group| PickerIcon {
+                base64: get_ico_base64(&group.ico),
+                index: group.index,
+                id: group.id,
+                sizes: group.sizes,
+            })
+            .collect::<Vec<_>>();
+        ensure!(!icons.is_empty(), "No icons in {pe_path:?}");
+
+        Ok(IconPicker {
+            pe_path: pe_path.to_path_buf(),
+            target,
+            icons,
+            by_id: false,
+        })
+    }
+}
+
+/// Show the icon groups of `pe_path`, the picked one goes to `target`
+pub fn open_icon_picker(
+    mut show_icon_picker: Signal<Option<IconPicker>>,
+    pe_path: &Path,
+    target: IconTarget,
+) {
+    match IconPicker::new(pe_path, target) {
+        Ok(picker) => *show_icon_picker.write() = Some(picker),
+        Err(e) => {
+            error!("{e}");
+            notify(&format!("{}: {e}", t!("ERROR_READ_PE_ICONS")));
+        }
+    }
+}
+
+#[component]
+pub fn icon_picker(
+    mut show_icon_picker: Signal<Option<IconPicker>>,
+    link_list: Signal<LinkList>,
+    customize_icon: Signal<CustomizeIcon>,
+) -> Element {
+    let Some(picker_read) = show_icon_picker.read().clone() else {
+        return rsx!();
+    };
+
+    let file_name = picker_read
+        .pe_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    rsx! {
+        style { {include_str!("css/icon_picker.css")} }
+        div { class: "icon-picker-container",
+            div {
+                class: "icon-picker-modal",
+                onmousedown: |event| event.stop_propagation(), // 屏蔽拖拽
+                div { class: "head",
+                    span { title: "{picker_read.pe_path.display()}",
+                        {format!("{} - {file_name} ({})", t!("SELECT_PE_ICON"), picker_read.icons.len())}
+                    }
+                    button { onclick: move |_| *show_icon_picker.write() = None, "X" }
+                }
+                div { class: "icons",
+                    for (position, icon) in picker_read.icons.iter().enumerate() {
+                        button {
+                            key: "{icon.index}",
+                            title: format!(
+                                "{} {}\n{} {}\n{}",
+                                t!("ICON_INDEX"),
+                                icon.index,
+                                t!("ICON_RESOURCE_ID"),
+                                icon.id,
+                                icon.sizes.iter().map(|size| format!("{size}px")).collect::<Vec<_>>().join(", "),
+                            ),
+                            onclick: move |_| pick_icon(show_icon_picker, link_list, customize_icon, position),
+                            img { src: icon.base64.clone() }
+                            span { {icon.icon_index(picker_read.by_id).to_string()} }
+                        }
+                    }
+                }
+                div { class: "buttons",
+                    label { class: "by-id",
+                        input {
+                            r#type: "checkbox",
+                            checked: picker_read.by_id,
+                            onchange: move |event| {
+                                if let Some(picker) = show_icon_picker.write().as_mut() {
+                                    picker.by_id = event.checked();
+                                }
+                            },
+                        }
+                        {t!("PE_ICON_BY_ID")}
+                    }
+                    button { onclick: move |_| *show_icon_picker.write() = None, {t!("CANCEL")} }
+                }
+            }
+        }
+    }
+}
+
+fn pick_icon(
+    mut show_icon_picker: Signal<Option<IconPicker>>,
+    link_list: Signal<LinkList>,
+    mut customize_icon: Signal<CustomizeIcon>,
+    position: usize,
+) {
+    let Some(picker) = show_icon_picker.write().take() else {
+        return;
+    };
+    let icon = &picker.icons[position];
+    let icon_index = icon.icon_index(picker.by_id);
+
+    match picker.target {
+        IconTarget::Shortcut => {
+            match change_single_shortcut_icon(link_list, &picker.pe_path, icon_index) {
+                Ok(Some(name)) => notify(&format!("{}: {name}", t!("SUCCESS_CHANGE_ONE"))),
+                Err(e) => error!("Failed to change the shortcut icon - {e}"),
+                _ => {}
+            }
+        }
+        IconTarget::Customize { keep_link } => {
+            let mut customize_icon = customize_icon.write();
+            let link_prop = match keep_link {
+                true => customize_icon.link.get_or_insert_default(),
+                false => customize_icon.link.insert(LinkProp::default()),
+            };
+            link_prop.icon_path = picker.pe_path.to_string_lossy().into_owned();
+            link_prop.icon_index = icon_index.to_string();
+            link_prop.icon_base64 = icon.base64.clone();
+        }
+    }
+}
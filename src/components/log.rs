@@ -49,11 +49,11 @@ pub fn log() -> Element {
                 }
                 button {
                     onclick: move |_| {
-                        if let Some(path) = log_path.read().as_ref().and_then(|p| p.parent()) {
-                            if let Err(e) = opener::open(path) {
-                                error!("{e}");
-                                notify(&format!("{e}"));
-                            }
+                        if let Some(path) = log_path.read().as_ref().and_then(|p| p.parent())
+                            && let Err(e) = opener::open(path)
+                        {
+                            error!("{e}");
+                            notify(&format!("{e}"));
                         }
                     },
                     class: "animated-button",
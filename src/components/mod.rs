@@ -12,3 +12,11 @@ pub mod status;
 pub mod properties;
 
 pub mod msgbox;
+
+pub mod create;
+
+pub mod plan;
+
+pub mod orphans;
+
+pub mod icon_picker;
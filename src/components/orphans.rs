@@ -0,0 +1,104 @@
+use crate::{
+    link::{
+        icon_gc::{OrphanIcon, gc_sources, remove_orphan_icons},
+        list::LinkList,
+    },
+    utils::notify,
+};
+
+use std::path::Path;
+
+use dioxus::prelude::*;
+use log::*;
+use rfd::FileDialog;
+use rust_i18n::t;
+
+#[component]
+pub fn orphans(
+    mut show_orphans: Signal<Option<Vec<OrphanIcon>>>,
+    link_list: Signal<LinkList>,
+) -> Element {
+    let Some(orphans_read) = show_orphans.read().clone() else {
+        return rsx!();
+    };
+
+    let total = orphans_read.iter().map(|orphan| orphan.size).sum::<u64>();
+
+    rsx! {
+        style { {include_str!("css/orphans.css")} }
+        div { class: "orphans-container",
+            div {
+                class: "orphans-modal",
+                onmousedown: |event| event.stop_propagation(), // 屏蔽拖拽
+                div { class: "head",
+                    span {
+                        {
+                            format!(
+                                "{} ({}, {:.2} KB)",
+                                t!("ORPHAN_ICONS"),
+                                orphans_read.len(),
+                                total as f64 / 1024.0,
+                            )
+                        }
+                    }
+                    button { onclick: move |_| *show_orphans.write() = None, "X" }
+                }
+                div { class: "rows",
+                    for orphan in orphans_read.iter() {
+                        div { key: "{orphan.path.display()}", class: "row",
+                            span { class: "name", title: "{orphan.path.display()}",
+                                {orphan.path.file_name().unwrap_or_default().to_string_lossy().into_owned()}
+                            }
+                            span { class: "size", {format!("{:.2} KB", orphan.size as f64 / 1024.0)} }
+                        }
+                    }
+                }
+                div { class: "buttons",
+                    button {
+                        onclick: move |_| {
+                            let Some(archive_path) = FileDialog::new()
+                                .set_title(t!("ARCHIVE_ORPHAN_ICONS"))
+                                .add_filter("zip", &["zip"])
+                                .set_file_name("LinkEcho-icons.zip")
+                                .save_file()
+                            else {
+                                return;
+                            };
+                            remove_orphans(show_orphans, link_list, Some(&archive_path));
+                        },
+                        {t!("ARCHIVE_ORPHAN_ICONS")}
+                    }
+                    button {
+                        class: "confirm",
+                        onclick: move |_| remove_orphans(show_orphans, link_list, None),
+                        {t!("DELETE_ORPHAN_ICONS")}
+                    }
+                    button { onclick: move |_| *show_orphans.write() = None, {t!("CANCEL")} }
+                }
+            }
+        }
+    }
+}
+
+fn remove_orphans(
+    mut show_orphans: Signal<Option<Vec<OrphanIcon>>>,
+    link_list: Signal<LinkList>,
+    archive_path: Option<&Path>,
+) {
+    let Some(orphans) = show_orphans.write().take() else {
+        return;
+    };
+    let sources = gc_sources(&link_list.read().source);
+    match remove_orphan_icons(&sources, &orphans, archive_path) {
+        Ok(summary) => notify(&format!(
+            "{}: {} ({:.2} KB)",
+            t!("SUCCESS_REMOVE_ORPHAN_ICONS"),
+            summary.removed,
+            summary.freed as f64 / 1024.0
+        )),
+        Err(e) => {
+            error!("{e}");
+            notify(&format!("{}: {e}", t!("ERROR_REMOVE_ORPHAN_ICONS")));
+        }
+    }
+}
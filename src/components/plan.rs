@@ -0,0 +1,244 @@
+use super::tabs::Tab;
+use crate::{
+    link::{
+        list::LinkList,
+        modify::{apply_match_plan, build_match_plan, pick_icon_pack},
+        packs::IconPacks,
+        plan::MatchPlan,
+    },
+    utils::notify,
+};
+
+use dioxus::prelude::*;
+use log::*;
+use rust_i18n::t;
+
+#[component]
+pub fn plan(
+    mut show_plan: Signal<Option<MatchPlan>>,
+    link_list: Signal<LinkList>,
+    mut current_tab: Signal<Tab>,
+) -> Element {
+    let Some(plan_read) = show_plan.read().clone() else {
+        return rsx!();
+    };
+
+    let accepted = plan_read.rows.iter().filter(|row| row.accepted).count();
+    let pack_count = plan_read.packs.dirs.len();
+
+    rsx! {
+        style { {include_str!("css/plan.css")} }
+        div { class: "plan-container",
+            div {
+                class: "plan-modal",
+                onmousedown: |event| event.stop_propagation(), // 屏蔽拖拽
+                div { class: "head",
+                    span { {format!("{} ({accepted}/{})", t!("PLAN_TITLE"), plan_read.rows.len())} }
+                    button { onclick: move |_| *show_plan.write() = None, "X" }
+                }
+                // 图标包，越靠前优先级越高
+                div { class: "packs",
+                    for (index , pack) in plan_read.packs.dirs.iter().enumerate() {
+                        div { key: "{pack.display()}", class: "pack",
+                            span { title: "{pack.display()}",
+                                {format!("{}. {}", index + 1, IconPacks::name(pack))}
+                            }
+                            button {
+                                disabled: index == 0,
+                                onclick: move |_| {
+                                    update_packs(show_plan, link_list, |packs| packs.raise(index))
+                                },
+                                "↑"
+                            }
+                            button {
+                                disabled: index + 1 == pack_count,
+                                onclick: move |_| {
+                                    update_packs(show_plan, link_list, |packs| packs.raise(index + 1))
+                                },
+                                "↓"
+                            }
+                            button {
+                                onclick: move |_| {
+                                    update_packs(show_plan, link_list, |packs| packs.remove(index))
+                                },
+                                "X"
+                            }
+                        }
+                    }
+                    button {
+                        class: "add",
+                        onclick: move |_| {
+                            if let Some(pack) = pick_icon_pack() {
+                                update_packs(show_plan, link_list, |packs| packs.add(pack));
+                            }
+                        },
+                        {t!("PLAN_ADD_PACK")}
+                    }
+                    label { class: "pinyin",
+                        input {
+                            r#type: "checkbox",
+                            checked: plan_read.packs.pinyin,
+                            onchange: move |event| {
+                                update_packs(show_plan, link_list, |packs| packs.pinyin = event.checked())
+                            },
+                        }
+                        {t!("PLAN_PINYIN")}
+                    }
+                    if !plan_read.variants.is_empty() {
+                        select {
+                            onchange: move |event| {
+                                let value = event.value();
+                                update_packs(
+                                    show_plan,
+                                    link_list,
+                                    |packs| packs.variant = (!value.is_empty()).then_some(value),
+                                );
+                            },
+                            option {
+                                value: "",
+                                selected: plan_read.packs.variant.is_none(),
+                                {t!("PLAN_DEFAULT_VARIANT")}
+                            }
+                            for variant in plan_read.variants.iter() {
+                                option {
+                                    value: "{variant}",
+                                    selected: plan_read.packs.variant.as_ref() == Some(variant),
+                                    {variant.clone()}
+                                }
+                            }
+                        }
+                    }
+                }
+                if plan_read.rows.is_empty() {
+                    span { class: "hint", {t!("NO_MATCHES")} }
+                } else {
+                    div { class: "rows",
+                        for (index , row) in plan_read.rows.iter().enumerate() {
+                            div {
+                                key: "{row.link_path}",
+                                class: if row.accepted { "row" } else { "row rejected" },
+                                input {
+                                    r#type: "checkbox",
+                                    checked: row.accepted,
+                                    onchange: move |event| {
+                                        if let Some(plan) = show_plan.write().as_mut() {
+                                            plan.rows[index].accepted = event.checked();
+                                        }
+                                    },
+                                }
+                                span { class: "name", title: "{row.link_path}", {row.link_name.clone()} }
+                                // 选中备选图标即与当前图标互换
+                                select {
+                                    title: "{row.icon.path.display()}",
+                                    onchange: move |event| {
+                                        if let (Some(plan), Ok(alternative)) = (
+                                            show_plan.write().as_mut(),
+                                            event.value().parse::<usize>(),
+                                        ) {
+                                            plan.rows[index].choose(alternative);
+                                        }
+                                    },
+                                    option { value: "", selected: true, {row.icon.name.clone()} }
+                                    for (alternative , icon) in row.alternatives.iter().enumerate() {
+                                        option { value: "{alternative}",
+                                            {
+                                                format!(
+                                                    "{} ({:.2}, {}, {})",
+                                                    icon.name,
+                                                    icon.score,
+                                                    icon.key.name(),
+                                                    plan_read.pack_name(icon).unwrap_or_default(),
+                                                )
+                                            }
+                                        }
+                                    }
+                                }
+                                span { class: "pack", {plan_read.pack_name(&row.icon).unwrap_or_default()} }
+                                span { class: "kind", {row.match_by()} }
+                                span { class: "score", {format!("{:.2}", row.icon.score)} }
+                            }
+                        }
+                    }
+                }
+                // 同名图标中未被使用的文件
+                if !plan_read.duplicates.is_empty() {
+                    details { class: "duplicates",
+                        summary { {format!("{} ({})", t!("DUPLICATE_ICONS"), plan_read.duplicates.len())} }
+                        for duplicate in plan_read.duplicates.iter() {
+                            div { key: "{duplicate.kept.display()}", class: "duplicate",
+                                span { title: "{duplicate.kept.display()}", {duplicate.name.clone()} }
+                                for shadowed in duplicate.shadowed.iter() {
+                                    span { class: "shadowed", {shadowed.display().to_string()} }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "buttons",
+                    button {
+                        onclick: move |_| {
+                            if let Some(plan) = show_plan.write().as_mut() {
+                                plan.rows.iter_mut().for_each(|row| row.accepted = true);
+                            }
+                        },
+                        {t!("PLAN_ACCEPT_ALL")}
+                    }
+                    button {
+                        onclick: move |_| {
+                            if let Some(plan) = show_plan.write().as_mut() {
+                                plan.rows.iter_mut().for_each(|row| row.accepted = false);
+                            }
+                        },
+                        {t!("PLAN_REJECT_ALL")}
+                    }
+                    button {
+                        class: "confirm",
+                        disabled: accepted == 0,
+                        onclick: move |_| {
+                            let Some(plan) = show_plan.write().take() else {
+                                return;
+                            };
+                            match apply_match_plan(link_list, &plan) {
+                                Ok(count) => {
+                                    notify(&format!("{}: {count}", t!("SUCCESS_CHANGE_ALL")));
+                                    *current_tab.write() = Tab::Home;
+                                }
+                                Err(e) => {
+                                    error!("{e}");
+                                    notify(&t!("ERROR_CHANGE_ALL"));
+                                }
+                            }
+                        },
+                        {t!("PLAN_APPLY")}
+                    }
+                    button { onclick: move |_| *show_plan.write() = None, {t!("CANCEL")} }
+                }
+            }
+        }
+    }
+}
+
+/// Save the edited pack list and match again
+fn update_packs(
+    mut show_plan: Signal<Option<MatchPlan>>,
+    link_list: Signal<LinkList>,
+    edit: impl FnOnce(&mut IconPacks),
+) {
+    let mut packs = show_plan
+        .read()
+        .as_ref()
+        .map(|plan| plan.packs.clone())
+        .unwrap_or_default();
+    edit(&mut packs);
+
+    match packs
+        .save()
+        .and_then(|()| build_match_plan(link_list, &packs))
+    {
+        Ok(plan) => *show_plan.write() = Some(plan),
+        Err(e) => {
+            error!("{e}");
+            notify(&t!("ERROR_CHANGE_ALL"));
+        }
+    }
+}
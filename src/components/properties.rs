@@ -1,10 +1,20 @@
-use crate::link::list::LinkList;
+use crate::{
+    link::{
+        edit::{LinkEdit, ShowCommand, edit_selected_shortcut},
+        list::LinkList,
+    },
+    utils::notify,
+};
 
 use dioxus::prelude::*;
+use log::*;
 use rust_i18n::t;
 
 #[component]
 pub fn properties(mut show_prop: Signal<bool>, mut link_list: Signal<LinkList>) -> Element {
+    // 未修改时为 None，显示快捷方式当前的属性
+    let mut link_edit: Signal<Option<LinkEdit>> = use_signal(|| None);
+
     fn rsx_info(label: &str, value: &str) -> Element {
         rsx! {
             div { class: "item",
@@ -13,9 +23,68 @@ pub fn properties(mut show_prop: Signal<bool>, mut link_list: Signal<LinkList>)
         }
     }
 
+    fn rsx_edit(
+        label: &str,
+        edit: &LinkEdit,
+        mut link_edit: Signal<Option<LinkEdit>>,
+        field: fn(&mut LinkEdit) -> &mut String,
+    ) -> Element {
+        let mut edit = edit.clone();
+        let value = field(&mut edit).clone();
+        rsx! {
+            div { class: "item edit",
+                label { "{label}" }
+                input {
+                    r#type: "text",
+                    autocomplete: "off",
+                    value,
+                    oninput: move |event| {
+                        let mut edit = edit.clone();
+                        *field(&mut edit) = event.value();
+                        link_edit.set(Some(edit));
+                    },
+                }
+            }
+        }
+    }
+
     if *show_prop.read() {
         if let Some(index) = link_list.read().state.select {
-            let item = &link_list.read().items[index];
+            let item = link_list.read().items[index].clone();
+            let edit = link_edit
+                .read()
+                .clone()
+                .unwrap_or_else(|| LinkEdit::from_prop(&item));
+
+            let arguments = rsx_edit(
+                &t!("ARGUMENTS"),
+                &edit,
+                link_edit,
+                |e| &mut e.arguments,
+            );
+            let working_dir = rsx_edit(
+                &t!("WORKING_DIR"),
+                &edit,
+                link_edit,
+                |e| &mut e.working_dir,
+            );
+            let description = rsx_edit(
+                &t!("DESCRIPTION"),
+                &edit,
+                link_edit,
+                |e| &mut e.description,
+            );
+            let hotkey = rsx_edit(&t!("HOTKEY"), &edit, link_edit, |e| &mut e.hotkey);
+            let show_command = edit.show_command;
+            // 保留快捷方式中其他的运行方式
+            let show_commands = ShowCommand::ALL
+                .into_iter()
+                .chain(Some(item.show_command).filter(|c| !ShowCommand::ALL.contains(c)))
+                .collect::<Vec<_>>();
+            let show_commands_for_select = show_commands.clone();
+            let edit_for_select = edit.clone();
+            let is_modified = link_edit.read().is_some();
+
             rsx! {
                 style { {include_str!("css/properties.css")} }
                 div { class: "properties-container",
@@ -24,7 +93,10 @@ pub fn properties(mut show_prop: Signal<bool>, mut link_list: Signal<LinkList>)
                             span { {item.name.clone()} }
                             button {
                                 onmousedown: |event| event.stop_propagation(), // 屏蔽拖拽
-                                onclick: move |_| *show_prop.write() = false,
+                                onclick: move |_| {
+                                    link_edit.set(None);
+                                    *show_prop.write() = false;
+                                },
                                 "X"
                             }
                         }
@@ -34,11 +106,55 @@ pub fn properties(mut show_prop: Signal<bool>, mut link_list: Signal<LinkList>)
                             {rsx_info(&t!("FILE_PATH"), &item.path)}
                             {rsx_info(&t!("TARGET_PATH"), &item.target_path)}
                             {rsx_info(&t!("ICON_PATH"), &item.icon_path)}
-                            {rsx_info(&t!("ARGUMENTS"), &item.arguments)}
+                            {arguments}
+                            {working_dir}
+                            {description}
+                            {hotkey}
+                            div { class: "item edit",
+                                label { {t!("SHOW_COMMAND")} }
+                                select {
+                                    onchange: move |event| {
+                                        let mut edit = edit_for_select.clone();
+                                        edit.show_command = show_commands_for_select
+                                            .iter()
+                                            .copied()
+                                            .find(|s| s.to_u32().to_string() == event.value())
+                                            .unwrap_or_default();
+                                        link_edit.set(Some(edit));
+                                    },
+                                    for command in show_commands {
+                                        option {
+                                            value: command.to_u32().to_string(),
+                                            selected: command == show_command,
+                                            {command.name()}
+                                        }
+                                    }
+                                }
+                            }
                             {rsx_info(&t!("FILE_SIZE"), &item.file_size)}
                             {rsx_info(&t!("CREATED_AT"), &item.created_at)}
                             {rsx_info(&t!("UPDATED_AT"), &item.updated_at)}
                             {rsx_info(&t!("ACCESSED_AT"), &item.accessed_at)}
+                            button {
+                                class: "save",
+                                disabled: !is_modified,
+                                onclick: move |_| {
+                                    let Some(edit) = link_edit.read().clone() else {
+                                        return;
+                                    };
+                                    match edit_selected_shortcut(link_list, &edit) {
+                                        Ok(name) => {
+                                            notify(&format!("{name}: {}", t!("SUCCESS_EDIT_PROPERTIES")));
+                                            link_edit.set(None);
+                                        }
+                                        Err(e) => {
+                                            error!("{e}");
+                                            notify(&format!("{e}"));
+                                        }
+                                    }
+                                },
+                                {t!("SAVE")}
+                            }
                         }
                     }
                 }
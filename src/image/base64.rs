@@ -1,3 +1,8 @@
+use super::{
+    ico::IcoFile,
+    pe_icon::{is_pe_icon_file, pe_icon},
+};
+
 use std::{fs::File, io::Read, path::Path};
 
 use anyhow::{Context, Result, anyhow};
@@ -5,6 +10,8 @@ use base64::prelude::{BASE64_STANDARD, Engine};
 use image::ImageFormat;
 use log::{error, warn};
 
+/// Frame size of .ico previews
+const PREVIEW_SIZE: u32 = 256;
 const FILE_NOT_EXIST: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAIAAAACACAMAAAD04JH5AAAAVFBMVEUAAACXl5eamprg4ODf39+Xl5ejo6PU1NSZmZmampqYmJiZmZnLy8u/v7+qqqqsrKzQ0NDX19eZmZn////g4OCzs7Ompqby8vLMzMzl5eXZ2dm/v79UJhU+AAAAEnRSTlMAQL/gICDjw99gn4C208vnz7cEViRYAAACDUlEQVR42u3b3W6jMBCG4fwsWX7S7nYcj8dw//fZk6qTZgDJlf21auc99EmeAMYGiYPn2bppGk91ev7ziZ8/Ub0u52LBQDW73EoFR6oMKBX0tQGFgoFqAwoFp+qAQgFVBxgBGlAm6OoDygTHRgAV4AFWgAeoAA+gmxGAAWcjAANebkaABTzdjAAL6M9GgAXQxQjAAPpnBGAA9f/PRoABKOH56Xr9+961wwJsRwc4wAEOcIADfg6AOcqyRNKQAJYc3kqiCBSA5/ChmbEACY8lAQI4h5USowCcgmYEAEAOGyUMIIfNZgRgCTstAEC6v/IlMsv9SEOAnYDyNsRmrCVA/25cmxepNWC5/32NdbgxYNYLfn1c2gJ0DjLR6iHIbQF6CWzJUmPA5i1HwADZnJ6gU7B8FUC2DvQMAnDemO2oWUAsOc28fYOaWwH2Y7MeggB2j0YaDsDJbEmQgChBY9JaA/ThRBOCAjg8lAkJsNvTFMGA8FAkLCDu/X88IJMJCUgLaXhAFtLwAL34HOAAMIDNEggGUDI7VDAgmjMABlBMZgnAADSmjX7Hy2oHELGYt/VQgJidMBYg5mEEDFh/KfKLFiMH+HL8DZZjzr4ckwMc4AAHOMABDqgK6AhS930BB4K0+6kXoHEHMBCgYQfQ9dS8HvCxV/kk1CZq3ID86NXW2ylo64ZxPDVonKbu4HmmV5qQRJe+53ewAAAAAElFTkSuQmCC";
 
 #[derive(Debug, Clone, Copy)]
@@ -63,6 +70,42 @@ pub fn get_img_base64_by_path(path: impl AsRef<Path>) -> String {
     }
 }
 
+/// Like `get_img_base64_by_path`, the icon of a program or library is the group at `icon_index`
+pub fn get_icon_base64_by_location(path: impl AsRef<Path>, icon_index: i32) -> String {
+    let path = path.as_ref();
+    if !is_pe_icon_file(path) || !path.is_file() {
+        return get_img_base64_by_path(path);
+    }
+    match pe_icon(path, icon_index) {
+        Ok(group) => get_ico_base64(&group.ico),
+        Err(e) => {
+            error!("Failed to process image: {e:?}");
+            FILE_NOT_EXIST.to_owned()
+        }
+    }
+}
+
+/// Data URI of the frame of an .ico file that suits previews best, the whole file if it can't be decoded
+///
+/// The webview would pick any frame of the whole file.
+pub fn get_ico_base64(ico: &[u8]) -> String {
+    match IcoFile::parse(ico).and_then(|icon| icon.best_frame(PREVIEW_SIZE).to_png()) {
+        Ok(png) => format!(
+            "data:{};base64,{}",
+            ImageMediaType::Png.mime_type(),
+            BASE64_STANDARD.encode(png)
+        ),
+        Err(e) => {
+            warn!("Failed to decode icon: {e}");
+            format!(
+                "data:{};base64,{}",
+                ImageMediaType::Ico.mime_type(),
+                BASE64_STANDARD.encode(ico)
+            )
+        }
+    }
+}
+
 fn try_get_img_base64(path: impl AsRef<Path>) -> Result<String> {
     let path = path.as_ref();
     let exists = path
@@ -83,6 +126,11 @@ fn try_get_img_base64(path: impl AsRef<Path>) -> Result<String> {
 
     match ImageMediaType::from_extension(ext) {
         Some(ImageMediaType::Svg) => read_and_encode(path, "image/svg+xml", read_svg_file),
+        Some(ImageMediaType::Ico) => {
+            let data = std::fs::read(path)
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            Ok(get_ico_base64(&data))
+        }
         Some(img_type) => {
             let mime = img_type.mime_type();
             read_and_encode(path, mime, |p| read_binary_file(p, img_type))
@@ -126,7 +174,19 @@ fn read_binary_file(path: &Path, img_type: ImageMediaType) -> Result<Vec<u8>> {
     Ok(data)
 }
 
+/// Icons of programs and libraries are read from their resources, other files are drawn by the shell
 fn handle_unknown_type(path: &Path) -> Result<String> {
+    if is_pe_icon_file(path) {
+        match pe_icon(path, 0) {
+            Ok(group) => return Ok(get_ico_base64(&group.ico)),
+            Err(e) => warn!("{e}"),
+        }
+    }
+    shell_icon_base64(path)
+}
+
+#[cfg(windows)]
+fn shell_icon_base64(path: &Path) -> Result<String> {
     windows_icons::get_icon_base64_by_path(path)
         .map(|icon| format!("data:image/png;base64,{icon}"))
         .map_err(|e| {
@@ -134,3 +194,10 @@ fn handle_unknown_type(path: &Path) -> Result<String> {
             anyhow!("{e}")
         })
 }
+
+/// Only the Windows shell draws icons of arbitrary files - 仅 Windows 外壳可绘制任意文件的图标
+#[cfg(not(windows))]
+fn shell_icon_base64(path: &Path) -> Result<String> {
+    warn!("Unknown file type: {path:?}");
+    Err(anyhow!("No shell icon for {path:?}"))
+}
@@ -0,0 +1,132 @@
+use super::{
+    background::get_background_image,
+    ico::load_ico,
+    ico_profile::IcoProfile,
+    icongen::{create_frames, load_svg, save_ico},
+    pe_icon::{PE_ICON_EXT, pe_icon},
+    rounded_corners::add_rounded_corners,
+};
+use crate::{link::conversion_cache::ICONS_DIR, utils::ensure_local_app_folder_exists};
+
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{Result, anyhow};
+use image::{
+    DynamicImage, RgbaImage,
+    imageops::{FilterType, overlay, resize},
+};
+use serde::{Deserialize, Serialize};
+
+/// Scaling, rounded corners and background drawn over an icon - 图标样式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IconStyle {
+    /// size: 0 ~ 100
+    pub icon_scaling: u32,
+    pub icon_borders_radius: u32,
+    /// (color: String, scaling: u32, borders_radius: u32)
+    pub background: Option<(String, u32, u32)>,
+}
+
+impl Default for IconStyle {
+    fn default() -> Self {
+        Self {
+            icon_scaling: 100,
+            icon_borders_radius: 0,
+            background: None,
+        }
+    }
+}
+
+/// Draw the icon with the style and save it as `icons\{name}.ico` in the local app folder
+///
+/// `icon_index` picks the icon group of a program or library.
+pub fn render_styled_icon(
+    icon_path: &str,
+    icon_index: i32,
+    style: &IconStyle,
+    name: &str,
+) -> Result<String> {
+    let icon_image = get_customize_icon_image(
+        icon_path,
+        icon_index,
+        style.icon_scaling,
+        style.icon_borders_radius,
+    )?;
+    let background_image = style
+        .background
+        .clone()
+        .map(get_background_image)
+        .and_then(Result::ok);
+
+    save_customize_icon(icon_image, background_image, name)
+}
+
+/// `icon_index` picks the icon group of a program or library
+pub fn get_customize_icon_image(
+    icon_path: &str,
+    icon_index: i32,
+    scaling: u32,
+    radius: u32,
+) -> Result<RgbaImage> {
+    let icon_sizes = 256 * scaling / 100;
+
+    let icon_image_ext = Path::new(icon_path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+
+    let icon_image = match icon_image_ext.as_str() {
+        "svg" => load_svg(icon_path, &[256])?.to_rgba8(),
+        "ico" => load_ico(Path::new(icon_path), 256)?,
+        "png" | "bmp" | "tiff" | "webp " => image::open(icon_path)?.to_rgba8(),
+        ext if PE_ICON_EXT.contains(&ext) => pe_icon(Path::new(icon_path), icon_index)?.image()?,
+        #[cfg(windows)]
+        "lnk" => windows_icons::get_icon_by_path(icon_path)
+            .map_err(|e| anyhow!("Failed to get the icon image. {e}"))?,
+        _ => {
+            return Err(anyhow!(
+                "The customize icon is not an image、lnk、exe or dll."
+            ));
+        }
+    };
+
+    let icon_image = resize(&icon_image, icon_sizes, icon_sizes, FilterType::Triangle);
+
+    Ok(add_rounded_corners(&DynamicImage::from(icon_image), radius))
+}
+
+pub fn save_customize_icon(
+    icon_image: RgbaImage,
+    background_image: Option<RgbaImage>,
+    name: &str,
+) -> Result<String> {
+    let mut combined_image = RgbaImage::new(256, 256);
+
+    if let Some(bg_image) = background_image {
+        let (bg_width, bg_height) = bg_image.dimensions();
+        let bg_x = (256 - bg_width) as i64 / 2;
+        let bg_y = (256 - bg_height) as i64 / 2;
+        overlay(&mut combined_image, &bg_image, bg_x, bg_y);
+    }
+
+    let (icon_width, icon_height) = icon_image.dimensions();
+    let icon_x = (256 - icon_width) as i64 / 2;
+    let icon_y = (256 - icon_height) as i64 / 2;
+    overlay(&mut combined_image, &icon_image, icon_x, icon_y);
+
+    let dyn_combined_image = DynamicImage::from(combined_image);
+    let frames = create_frames(
+        &dyn_combined_image,
+        &IcoProfile::load()?.frames(),
+        FilterType::Triangle,
+    )?;
+
+    let app_data_path = ensure_local_app_folder_exists().expect("Failed to get the app data path");
+    let icon_data_path = app_data_path.join(ICONS_DIR).join(format!("{name}.ico"));
+
+    save_ico(frames, &icon_data_path)?;
+
+    Ok(icon_data_path.to_string_lossy().into_owned())
+}
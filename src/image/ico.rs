@@ -0,0 +1,569 @@
+use std::{fmt, io::Cursor, path::Path};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use image::{ImageFormat, Rgba, RgbaImage};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Size of `ICONDIR`
+const DIR_HEADER_SIZE: usize = 6;
+/// Size of `ICONDIRENTRY`
+const DIR_ENTRY_SIZE: usize = 16;
+/// Size of `BITMAPINFOHEADER`, later versions of the header are longer
+const BMP_HEADER_SIZE: usize = 40;
+/// Larger frames are treated as corrupt
+const MAX_BMP_SIDE: usize = 4096;
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+/// How a frame is stored in the .ico file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameEncoding {
+    Png,
+    /// A `BITMAPINFOHEADER` without file header, the color bitmap followed by a 1-bit AND mask
+    Bmp,
+}
+
+/// One image of an .ico file
+#[derive(Debug, Clone)]
+pub struct IconFrame {
+    pub width: u32,
+    pub height: u32,
+    /// Bits per pixel from the PNG or BMP header, the directory entry often leaves it 0
+    pub bit_depth: u16,
+    pub encoding: FrameEncoding,
+    data: Vec<u8>,
+}
+
+impl fmt::Display for IconFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoding = match self.encoding {
+            FrameEncoding::Png => "PNG",
+            FrameEncoding::Bmp => "BMP",
+        };
+        write!(
+            f,
+            "{}x{} {}-bit {encoding}",
+            self.width, self.height, self.bit_depth
+        )
+    }
+}
+
+impl IconFrame {
+    fn new(data: Vec<u8>) -> Result<Self> {
+        if data.starts_with(PNG_SIGNATURE) {
+            // IHDR紧随PNG签名：宽、高（大端序）、位深、颜色类型
+            ensure!(data.len() >= 26, "Truncated PNG frame");
+            let channels = match data[25] {
+                2 => 3,
+                4 => 2,
+                6 => 4,
+                _ => 1,
+            };
+            return Ok(IconFrame {
+                width: u32::from_be_bytes([data[16], data[17], data[18], data[19]]),
+                height: u32::from_be_bytes([data[20], data[21], data[22], data[23]]),
+                bit_depth: data[24] as u16 * channels,
+                encoding: FrameEncoding::Png,
+                data,
+            });
+        }
+
+        let header = BmpHeader::parse(&data)?;
+        Ok(IconFrame {
+            width: header.width,
+            height: header.height,
+            bit_depth: header.bit_count,
+            encoding: FrameEncoding::Bmp,
+            data,
+        })
+    }
+
+    fn side(&self) -> u32 {
+        self.width.max(self.height)
+    }
+
+    pub fn decode(&self) -> Result<RgbaImage> {
+        match self.encoding {
+            FrameEncoding::Png => {
+                let image = image::load_from_memory_with_format(&self.data, ImageFormat::Png)
+                    .context("Invalid PNG frame")?;
+                Ok(image.to_rgba8())
+            }
+            FrameEncoding::Bmp => decode_bmp(&self.data),
+        }
+    }
+
+    /// The frame as a PNG file, a BMP frame is decoded and encoded again
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        if self.encoding == FrameEncoding::Png {
+            return Ok(self.data.clone());
+        }
+        let mut png = Vec::new();
+        self.decode()?
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .context("Failed to encode frame")?;
+        Ok(png)
+    }
+}
+
+/// Every frame of an .ico file, as stored - ICO解码
+#[derive(Debug, Clone)]
+pub struct IcoFile {
+    pub frames: Vec<IconFrame>,
+}
+
+impl IcoFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read {path:?}"))?;
+        IcoFile::parse(&data).with_context(|| format!("Invalid icon {path:?}"))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        ensure!(
+            data.len() >= DIR_HEADER_SIZE && data[..4] == [0, 0, 1, 0],
+            "Not an .ico file"
+        );
+        let count = read_u16(data, 4) as usize;
+
+        let frames = (0..count)
+            .map(|n| {
+                let start = DIR_HEADER_SIZE + n * DIR_ENTRY_SIZE;
+                let entry = data
+                    .get(start..start + DIR_ENTRY_SIZE)
+                    .context("Truncated icon directory")?;
+                let (size, offset) = (read_u32(entry, 8) as usize, read_u32(entry, 12) as usize);
+                let frame = data
+                    .get(offset..offset.saturating_add(size))
+                    .with_context(|| anyhow!("Frame {n} is out of the file"))?;
+                IconFrame::new(frame.to_vec()).with_context(|| anyhow!("Invalid frame {n}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(!frames.is_empty(), "The icon has no frames");
+
+        Ok(IcoFile { frames })
+    }
+
+    /// The smallest frame not smaller than `size`, or the largest one, the deepest colors first
+    pub fn best_frame(&self, size: u32) -> &IconFrame {
+        self.frames
+            .iter()
+            .max_by(|a, b| {
+                let fits = |frame: &IconFrame| frame.side() >= size;
+                fits(a)
+                    .cmp(&fits(b))
+                    .then_with(|| match fits(a) {
+                        true => b.side().cmp(&a.side()),
+                        false => a.side().cmp(&b.side()),
+                    })
+                    .then_with(|| a.bit_depth.cmp(&b.bit_depth))
+            })
+            .expect("An icon has at least one frame")
+    }
+}
+
+/// The frame of an .ico file that suits `size` best, decoded
+pub fn load_ico(path: &Path, size: u32) -> Result<RgbaImage> {
+    IcoFile::open(path)?.best_frame(size).decode()
+}
+
+/// A 32-bit BMP frame: `BITMAPINFOHEADER`, bottom-up BGRA rows and an AND mask of the transparent pixels
+pub fn encode_bmp_frame(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mask_stride = width.div_ceil(32) * 4;
+    let image_size = (width * 4 + mask_stride) * height;
+
+    let mut data = Vec::with_capacity(BMP_HEADER_SIZE + image_size);
+    data.extend_from_slice(&(BMP_HEADER_SIZE as u32).to_le_bytes());
+    data.extend_from_slice(&(width as i32).to_le_bytes());
+    // 高度包含AND掩码
+    data.extend_from_slice(&(height as i32 * 2).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.extend_from_slice(&BI_RGB.to_le_bytes());
+    data.extend_from_slice(&(image_size as u32).to_le_bytes());
+    // 分辨率与调色板均不使用
+    data.extend_from_slice(&[0; 16]);
+
+    for row in image.rows().rev() {
+        for pixel in row {
+            let [r, g, b, a] = pixel.0;
+            data.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    for row in image.rows().rev() {
+        let mut mask = vec![0u8; mask_stride];
+        for (x, pixel) in row.enumerate() {
+            if pixel[3] == 0 {
+                mask[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        data.extend_from_slice(&mask);
+    }
+    data
+}
+
+struct BmpHeader {
+    header_size: usize,
+    width: u32,
+    /// Height of the image, the header counts the AND mask too
+    height: u32,
+    bit_count: u16,
+    compression: u32,
+    colors_used: u32,
+}
+
+impl BmpHeader {
+    fn parse(data: &[u8]) -> Result<Self> {
+        ensure!(data.len() >= BMP_HEADER_SIZE, "Truncated BMP frame");
+        let header_size = read_u32(data, 0) as usize;
+        ensure!(
+            header_size >= BMP_HEADER_SIZE && header_size <= data.len(),
+            "Invalid BMP header size {header_size}"
+        );
+        Ok(BmpHeader {
+            header_size,
+            width: read_i32(data, 4).unsigned_abs(),
+            height: read_i32(data, 8).unsigned_abs() / 2,
+            bit_count: read_u16(data, 14),
+            compression: read_u32(data, 16),
+            colors_used: read_u32(data, 32),
+        })
+    }
+}
+
+/// Decode the color bitmap and apply the AND mask, rows are stored bottom-up
+///
+/// 32-bit frames carry their own alpha, the mask is only used when all of it is 0.
+fn decode_bmp(data: &[u8]) -> Result<RgbaImage> {
+    let header = BmpHeader::parse(data)?;
+    let (width, height) = (header.width as usize, header.height as usize);
+    let bits = header.bit_count as usize;
+    ensure!(
+        (1..=MAX_BMP_SIDE).contains(&width) && (1..=MAX_BMP_SIDE).contains(&height),
+        "Invalid BMP size {width}x{height}"
+    );
+    ensure!(
+        matches!(bits, 1 | 4 | 8 | 24 | 32),
+        "Unsupported bit depth {bits}"
+    );
+    ensure!(
+        header.compression == BI_RGB || (header.compression == BI_BITFIELDS && bits == 32),
+        "Unsupported BMP compression {}",
+        header.compression
+    );
+
+    let mut offset = header.header_size;
+    if header.compression == BI_BITFIELDS && header.header_size == BMP_HEADER_SIZE {
+        // 颜色掩码跟在40字节的头之后
+        offset += 12;
+    }
+
+    let palette = match bits {
+        1 | 4 | 8 => {
+            let count = match header.colors_used {
+                0 => 1 << bits,
+                count => count as usize,
+            };
+            let palette = data
+                .get(offset..offset + count * 4)
+                .context("Truncated BMP palette")?;
+            offset += count * 4;
+            palette
+                .chunks_exact(4)
+                .map(|c| Rgba([c[2], c[1], c[0], 255]))
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    let stride = (width * bits).div_ceil(32) * 4;
+    let pixels = data
+        .get(offset..offset + stride * height)
+        .context("Truncated BMP pixels")?;
+    offset += stride * height;
+
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    for y in 0..height {
+        let row = &pixels[(height - 1 - y) * stride..][..stride];
+        for x in 0..width {
+            let pixel = match bits {
+                32 => Rgba([row[x * 4 + 2], row[x * 4 + 1], row[x * 4], row[x * 4 + 3]]),
+                24 => Rgba([row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255]),
+                _ => {
+                    let bit = x * bits;
+                    let index = (row[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1) as u8;
+                    palette
+                        .get(index as usize)
+                        .copied()
+                        .unwrap_or(Rgba([0, 0, 0, 255]))
+                }
+            };
+            image.put_pixel(x as u32, y as u32, pixel);
+        }
+    }
+
+    let has_alpha = bits == 32 && image.pixels().any(|pixel| pixel[3] != 0);
+    if has_alpha {
+        return Ok(image);
+    }
+
+    // AND掩码：1为透明
+    let mask_stride = width.div_ceil(32) * 4;
+    let mask = data
+        .get(offset..offset + mask_stride * height)
+        .context("Truncated BMP mask")?;
+    for y in 0..height {
+        let row = &mask[(height - 1 - y) * mask_stride..][..mask_stride];
+        for x in 0..width {
+            let transparent = row[x / 8] & (0x80 >> (x % 8)) != 0;
+            image.get_pixel_mut(x as u32, y as u32)[3] = if transparent { 0 } else { 255 };
+        }
+    }
+    Ok(image)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    read_u32(data, offset) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: [u8; 4] = [0, 0, 0, 0];
+    const WHITE: [u8; 4] = [255, 255, 255, 0];
+    const RED: [u8; 4] = [0, 0, 255, 0];
+
+    /// `BITMAPINFOHEADER` of a frame `height` pixels high, without the AND mask
+    fn bmp_header(
+        width: i32,
+        height: i32,
+        bits: u16,
+        compression: u32,
+        colors_used: u32,
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(BMP_HEADER_SIZE as u32).to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&(height * 2).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bits.to_le_bytes());
+        data.extend_from_slice(&compression.to_le_bytes());
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&colors_used.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data
+    }
+
+    /// A BMP frame, `rows` and `mask` are bottom-up and padded to 4 bytes
+    fn bmp_frame(
+        width: i32,
+        bits: u16,
+        palette: &[[u8; 4]],
+        rows: &[&[u8]],
+        mask: &[u8],
+    ) -> Vec<u8> {
+        let height = rows.len() as i32;
+        let mut data = bmp_header(width, height, bits, BI_RGB, palette.len() as u32);
+        data.extend(palette.iter().flatten());
+        for row in rows {
+            data.extend_from_slice(row);
+        }
+        for &row in mask {
+            data.extend_from_slice(&[row, 0, 0, 0]);
+        }
+        data
+    }
+
+    fn ico(frames: &[&[u8]]) -> Vec<u8> {
+        let mut data = vec![0, 0, 1, 0];
+        data.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+        let mut offset = DIR_HEADER_SIZE + frames.len() * DIR_ENTRY_SIZE;
+        for frame in frames {
+            // 宽、高、颜色数与位深均留0，以帧头为准
+            data.extend_from_slice(&[0; 8]);
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += frame.len();
+        }
+        for frame in frames {
+            data.extend_from_slice(frame);
+        }
+        data
+    }
+
+    fn decode(frame: Vec<u8>) -> Result<RgbaImage> {
+        IcoFile::parse(&ico(&[&frame]))?.frames[0].decode()
+    }
+
+    fn rgba(bgr: [u8; 4], alpha: u8) -> Rgba<u8> {
+        Rgba([bgr[2], bgr[1], bgr[0], alpha])
+    }
+
+    #[test]
+    fn one_bit_frame_uses_the_and_mask() {
+        // 上行：白、黑；下行：黑、白；左上角透明
+        let frame = bmp_frame(
+            2,
+            1,
+            &[BLACK, WHITE],
+            &[&[0x40, 0, 0, 0], &[0x80, 0, 0, 0]],
+            &[0x00, 0x80],
+        );
+        let image = decode(frame).unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(*image.get_pixel(0, 0), rgba(WHITE, 0));
+        assert_eq!(*image.get_pixel(1, 0), rgba(BLACK, 255));
+        assert_eq!(*image.get_pixel(0, 1), rgba(BLACK, 255));
+        assert_eq!(*image.get_pixel(1, 1), rgba(WHITE, 255));
+    }
+
+    #[test]
+    fn palette_frames_are_decoded() {
+        let image = decode(bmp_frame(
+            3,
+            4,
+            &[BLACK, WHITE, RED],
+            &[&[0x12, 0x00, 0, 0]],
+            &[0x20],
+        ))
+        .unwrap();
+        assert_eq!(*image.get_pixel(0, 0), rgba(WHITE, 255));
+        assert_eq!(*image.get_pixel(1, 0), rgba(RED, 255));
+        assert_eq!(*image.get_pixel(2, 0), rgba(BLACK, 0));
+
+        let image = decode(bmp_frame(
+            2,
+            8,
+            &[BLACK, WHITE, RED],
+            &[&[2, 1, 0, 0]],
+            &[0],
+        ))
+        .unwrap();
+        assert_eq!(*image.get_pixel(0, 0), rgba(RED, 255));
+        assert_eq!(*image.get_pixel(1, 0), rgba(WHITE, 255));
+
+        // 超出调色板的索引为黑色
+        let image = decode(bmp_frame(1, 8, &[WHITE], &[&[5, 0, 0, 0]], &[0])).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn true_color_frames_are_decoded() {
+        let image = decode(bmp_frame(1, 24, &[], &[&[1, 2, 3, 0]], &[0x80])).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([3, 2, 1, 0]));
+
+        // 32位帧自带透明度时忽略AND掩码
+        let image = decode(bmp_frame(
+            2,
+            32,
+            &[],
+            &[&[1, 2, 3, 128, 4, 5, 6, 0]],
+            &[0x00],
+        ))
+        .unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([3, 2, 1, 128]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([6, 5, 4, 0]));
+
+        let image = decode(bmp_frame(2, 32, &[], &[&[1, 2, 3, 0, 4, 5, 6, 0]], &[0x40])).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([3, 2, 1, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([6, 5, 4, 0]));
+    }
+
+    #[test]
+    fn encoded_frame_is_decoded_back() {
+        let mut image = RgbaImage::from_pixel(33, 2, Rgba([10, 20, 30, 255]));
+        image.put_pixel(32, 1, Rgba([0, 0, 0, 0]));
+        image.put_pixel(0, 0, Rgba([1, 2, 3, 64]));
+        let frame = IconFrame::new(encode_bmp_frame(&image)).unwrap();
+        assert_eq!((frame.width, frame.height, frame.bit_depth), (33, 2, 32));
+        assert_eq!(frame.decode().unwrap(), image);
+    }
+
+    #[test]
+    fn best_frame_prefers_the_smallest_fitting_and_deepest() {
+        // 只解析帧头，不解码像素
+        let frame = |side: i32, bits: u16| bmp_header(side, side, bits, BI_RGB, 0);
+        let icon = IcoFile::parse(&ico(&[
+            &frame(16, 32),
+            &frame(32, 8),
+            &frame(32, 32),
+            &frame(48, 4),
+        ]))
+        .unwrap();
+        let best = |size| {
+            let frame = icon.best_frame(size);
+            (frame.width, frame.bit_depth)
+        };
+        assert_eq!(best(16), (16, 32));
+        assert_eq!(best(24), (32, 32));
+        assert_eq!(best(32), (32, 32));
+        assert_eq!(best(40), (48, 4));
+        assert_eq!(best(256), (48, 4));
+    }
+
+    #[test]
+    fn malformed_icons_are_errors() {
+        let valid = bmp_frame(1, 24, &[], &[&[1, 2, 3, 0]], &[0]);
+        assert!(IcoFile::parse(&ico(&[&valid])).is_ok());
+
+        let mut truncated_dir = ico(&[&valid]);
+        truncated_dir[4] = 2;
+        let mut out_of_file = ico(&[&valid]);
+        out_of_file[DIR_HEADER_SIZE + 8] = 0xff;
+        for data in [
+            &b""[..],
+            b"\0\0\x02\0\x01\0",
+            &truncated_dir,
+            &out_of_file,
+            &ico(&[]),
+            &ico(&[&valid[..20]]),
+            &ico(&[PNG_SIGNATURE]),
+        ] {
+            assert!(IcoFile::parse(data).is_err());
+        }
+
+        let mut header_too_long = valid.clone();
+        header_too_long[0] = 0xff;
+        let mut zero_width = valid.clone();
+        zero_width[4] = 0;
+        let mut huge = valid.clone();
+        huge[4..8].copy_from_slice(&i32::MAX.to_le_bytes());
+        let mut bits16 = valid.clone();
+        bits16[14] = 16;
+        let mut rle8 = bmp_frame(1, 8, &[WHITE], &[&[0, 0, 0, 0]], &[0]);
+        rle8[16] = 1;
+        let mut many_colors = bmp_frame(1, 8, &[WHITE], &[&[0, 0, 0, 0]], &[0]);
+        many_colors[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        for frame in [
+            header_too_long,
+            zero_width,
+            huge,
+            bits16,
+            rle8,
+            many_colors,
+            bmp_frame(1, 8, &[WHITE], &[], &[]),
+            valid[..BMP_HEADER_SIZE + 2].to_vec(),
+            valid[..valid.len() - 1].to_vec(),
+        ] {
+            assert!(
+                IconFrame::new(frame.clone())
+                    .and_then(|f| f.decode())
+                    .is_err()
+            );
+        }
+    }
+}
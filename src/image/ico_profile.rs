@@ -0,0 +1,110 @@
+use super::ico::FrameEncoding;
+use crate::utils::ensure_local_app_folder_exists;
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+const ICO_PROFILE_FILE: &str = "ico_profile.json";
+
+/// Size and encoding of one frame of the written .ico files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSpec {
+    pub size: u32,
+    /// PNG frames are 32-bit RGBA, BMP frames 32-bit BGRA with an AND mask
+    pub encoding: FrameEncoding,
+}
+
+/// Frames of the .ico files LinkEcho writes - 图标输出配置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IcoProfile {
+    /// PNG frames of every size
+    #[default]
+    Modern,
+    /// BMP frames up to 48px and a PNG 256px frame, for older shells and tools
+    Compatible,
+    /// Modern plus the sizes of the other scaling factors: 20, 24, 40, 72 and 96px
+    HighDpi,
+}
+
+impl IcoProfile {
+    pub const ALL: [IcoProfile; 3] = [
+        IcoProfile::Modern,
+        IcoProfile::Compatible,
+        IcoProfile::HighDpi,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IcoProfile::Modern => "modern",
+            IcoProfile::Compatible => "compatible",
+            IcoProfile::HighDpi => "high-dpi",
+        }
+    }
+
+    pub fn frames(self) -> Vec<FrameSpec> {
+        let (sizes, bmp_up_to): (&[u32], u32) = match self {
+            IcoProfile::Modern => (&[16, 32, 48, 64, 128, 256], 0),
+            IcoProfile::Compatible => (&[16, 32, 48, 256], 48),
+            IcoProfile::HighDpi => (&[16, 20, 24, 32, 40, 48, 64, 72, 96, 128, 256], 0),
+        };
+        sizes
+            .iter()
+            .map(|&size| FrameSpec {
+                size,
+                encoding: if size <= bmp_up_to {
+                    FrameEncoding::Bmp
+                } else {
+                    FrameEncoding::Png
+                },
+            })
+            .collect()
+    }
+
+    /// Whether any profile writes a frame of `size`
+    pub fn is_frame_size(size: u32) -> bool {
+        IcoProfile::ALL
+            .iter()
+            .any(|profile| profile.frames().iter().any(|frame| frame.size == size))
+    }
+
+    fn path() -> Result<PathBuf> {
+        Ok(ensure_local_app_folder_exists()?.join(ICO_PROFILE_FILE))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = IcoProfile::path()?;
+        if !path.is_file() {
+            return Ok(IcoProfile::default());
+        }
+        let data = std::fs::read(&path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    pub fn save(self) -> Result<()> {
+        let path = IcoProfile::path()?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(&self)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for IcoProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for IcoProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        IcoProfile::ALL
+            .into_iter()
+            .find(|profile| profile.name() == name)
+            .ok_or_else(|| anyhow!("Unknown ICO profile: {name}"))
+    }
+}
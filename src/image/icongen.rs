@@ -15,38 +15,112 @@
 //
 // Note: This file has been modified from the original version.
 
-use std::{ffi::OsStr, path::Path};
+use super::{
+    ico::{FrameEncoding, IcoFile, encode_bmp_frame, load_ico},
+    ico_profile::{FrameSpec, IcoProfile},
+};
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
 use image::codecs::ico::{IcoEncoder, IcoFrame};
-use image::{DynamicImage, Rgba, RgbaImage};
+use image::imageops::FilterType;
+use image::{DynamicImage, ExtendedColorType, Rgba, RgbaImage};
 use rayon::prelude::*;
 use resvg::tiny_skia;
 
-pub fn image_to_ico(image_path: &Path, output_path: &Path, name: &str) -> Result<()> {
-    let sizes = vec![16, 32, 48, 64, 128, 256];
-    let filter = image::imageops::FilterType::CatmullRom;
+const ICO_FILTER: FilterType = FilterType::CatmullRom;
+
+/// Everything besides the source that changes the converted icon, part of its cache key
+pub fn conversion_params(profile: IcoProfile) -> String {
+    format!("{:?} {ICO_FILTER:?}", profile.frames())
+}
 
+/// `size_sources` are used as they are for the frames of their size, the image is resampled for the others
+pub fn image_to_ico(
+    image_path: &Path,
+    size_sources: &BTreeMap<u32, PathBuf>,
+    output_path: &Path,
+    name: &str,
+    profile: IcoProfile,
+) -> Result<()> {
+    let frame_specs = profile.frames();
+    let sizes = frame_specs.iter().map(|spec| spec.size).collect::<Vec<_>>();
     let image = load_image(image_path, &sizes)?;
     check_image_dimensions(&image, name);
 
-    let frames = create_frames(&image, sizes, filter)?;
+    let frames = frame_specs
+        .par_iter()
+        .map(|spec| {
+            let sz = spec.size;
+            let exact_image = match size_sources.get(&sz) {
+                Some(source) => load_size_source(source, sz)?,
+                None => None,
+            };
+            let frame_image =
+                exact_image.unwrap_or_else(|| image.resize_exact(sz, sz, ICO_FILTER).to_rgba8());
+            encode_frame(&frame_image, spec)
+        })
+        .collect::<Result<Vec<_>>>()?;
     save_ico(frames, output_path)?;
 
     Ok(())
 }
 
+/// A per-size image of exactly `size` pixels, `None` when its dimensions don't match
+fn load_size_source(source: &Path, size: u32) -> Result<Option<RgbaImage>> {
+    let image = load_image(source, &[size])?;
+    if image.width() != size || image.height() != size {
+        log::warn!(
+            "{} is {}x{}, the {size}px frame is resampled instead",
+            source.display(),
+            image.width(),
+            image.height()
+        );
+        return Ok(None);
+    }
+    log::debug!("{size}px frame from {}", source.display());
+    Ok(Some(image.to_rgba8()))
+}
+
+/// Longest side in pixels, an SVG counts as the largest since it scales to any size
+///
+/// An .ico counts as its largest frame.
+pub fn image_side(image_path: &Path) -> u32 {
+    let ext = image_path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "svg" => u32::MAX,
+        "ico" => IcoFile::open(image_path).map_or(0, |icon| {
+            let frame = icon.best_frame(u32::MAX);
+            frame.width.max(frame.height)
+        }),
+        _ => image::image_dimensions(image_path).map_or(0, |(width, height)| width.max(height)),
+    }
+}
+
+/// An .ico is loaded from the frame that fits the largest size
 fn load_image(image_path: &Path, sizes: &[u32]) -> Result<DynamicImage> {
-    if image_path
+    let ext = image_path
         .extension()
         .and_then(OsStr::to_str)
         .map(str::to_lowercase)
-        .is_some_and(|ext| &ext == "svg")
-    {
-        load_svg(image_path, sizes)
-    } else {
-        image::open(image_path)
-            .with_context(|| format!("Failed to open file '{}'", image_path.display()))
+        .unwrap_or_default();
+    match ext.as_str() {
+        "svg" => load_svg(image_path, sizes),
+        "ico" => {
+            let max_size = *sizes.iter().max().unwrap_or(&256);
+            load_ico(image_path, max_size).map(DynamicImage::from)
+        }
+        _ => image::open(image_path)
+            .with_context(|| format!("Failed to open file '{}'", image_path.display())),
     }
 }
 
@@ -101,29 +175,32 @@ fn check_image_dimensions(image: &DynamicImage, name: &str) {
     }
 }
 
+/// Resize the image to every frame of the profile and encode it as PNG or BMP
 pub fn create_frames(
     image: &DynamicImage,
-    sizes: Vec<u32>,
-    filter: image::imageops::FilterType,
-) -> Result<Vec<IcoFrame>> {
-    let frames: Vec<Vec<u8>> = sizes
+    frame_specs: &[FrameSpec],
+    filter: FilterType,
+) -> Result<Vec<IcoFrame<'static>>> {
+    frame_specs
         .par_iter()
-        .map(|&sz| {
-            let resized_image = image.resize_exact(sz, sz, filter);
-            resized_image.to_rgba8().to_vec()
-        })
-        .collect();
-
-    frames
-        .par_iter()
-        .zip(sizes.par_iter())
-        .map(|(buf, &sz)| {
-            IcoFrame::as_png(buf.as_slice(), sz, sz, image.color().into())
-                .with_context(|| "Failed to encode frame")
+        .map(|spec| {
+            let resized_image = image.resize_exact(spec.size, spec.size, filter);
+            encode_frame(&resized_image.to_rgba8(), spec)
         })
         .collect()
 }
 
+fn encode_frame(image: &RgbaImage, spec: &FrameSpec) -> Result<IcoFrame<'static>> {
+    let sz = spec.size;
+    match spec.encoding {
+        FrameEncoding::Png => IcoFrame::as_png(image.as_raw(), sz, sz, ExtendedColorType::Rgba8),
+        FrameEncoding::Bmp => {
+            IcoFrame::with_encoded(encode_bmp_frame(image), sz, sz, ExtendedColorType::Rgba8)
+        }
+    }
+    .with_context(|| "Failed to encode frame")
+}
+
 pub fn save_ico(frames: Vec<IcoFrame>, output_path: &Path) -> Result<()> {
     let file = std::fs::File::create(output_path)
         .with_context(|| format!("Failed to create file '{}'", output_path.display()))?;
@@ -1,4 +1,8 @@
 pub mod background;
 pub mod base64;
+pub mod customize;
+pub mod ico;
+pub mod ico_profile;
 pub mod icongen;
+pub mod pe_icon;
 pub mod rounded_corners;
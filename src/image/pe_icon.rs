@@ -0,0 +1,326 @@
+use super::ico::IcoFile;
+
+use std::{ffi::OsStr, fmt, path::Path};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use editpe::{
+    Image, ResourceEntry, ResourceEntryName, ResourceTable,
+    constants::{RT_GROUP_ICON, RT_ICON},
+};
+use image::RgbaImage;
+use log::*;
+
+/// Files whose icons are read from their resources
+pub const PE_ICON_EXT: [&str; 5] = ["exe", "dll", "cpl", "ocx", "scr"];
+
+/// Size of `GRPICONDIR` and of `ICONDIR`
+const DIR_HEADER_SIZE: usize = 6;
+/// Size of `GRPICONDIRENTRY`, it ends with the RT_ICON id instead of a file offset
+const GROUP_ENTRY_SIZE: usize = 14;
+const ICO_ENTRY_SIZE: usize = 16;
+
+/// Name of an RT_GROUP_ICON resource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IconResourceId {
+    Id(u32),
+    Name(String),
+}
+
+impl fmt::Display for IconResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IconResourceId::Id(id) => write!(f, "#{id}"),
+            IconResourceId::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// One icon group of a PE file, rebuilt as a standalone multi-frame .ico - 程序图标组
+#[derive(Debug, Clone)]
+pub struct PeIconGroup {
+    /// Position among the groups, the icon index a shortcut stores
+    pub index: usize,
+    /// A shortcut refers to `Id(n)` by the negative icon index `-n`
+    pub id: IconResourceId,
+    /// Width of every frame
+    pub sizes: Vec<u32>,
+    pub ico: Vec<u8>,
+}
+
+impl PeIconGroup {
+    /// The 256px frame or the largest one, decoded
+    pub fn image(&self) -> Result<RgbaImage> {
+        IcoFile::parse(&self.ico)
+            .and_then(|icon| icon.best_frame(256).decode())
+            .with_context(|| format!("Failed to decode icon {}", self.id))
+    }
+}
+
+pub fn is_pe_icon_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| PE_ICON_EXT.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Every icon group of the PE file, in the order Windows counts icon indexes
+///
+/// A group that can't be rebuilt is skipped, the indexes of the others stay the same.
+pub fn pe_icon_groups(pe_path: &Path) -> Result<Vec<PeIconGroup>> {
+    let image = parse(pe_path)?;
+    let Some((groups, icons)) = icon_tables(&image) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(group_entries(groups)
+        .filter_map(|(index, id, group)| {
+            build_group(index, id, group, icons)
+                .inspect_err(|e| warn!("Invalid icon group #{index} in {pe_path:?}: {e}"))
+                .ok()
+        })
+        .collect())
+}
+
+/// The icon group of a shortcut's icon index, a negative index is a resource id
+pub fn pe_icon(pe_path: &Path, icon_index: i32) -> Result<PeIconGroup> {
+    let image = parse(pe_path)?;
+    let (groups, icons) =
+        icon_tables(&image).with_context(|| format!("No icons in {pe_path:?}"))?;
+
+    let (index, id, group) = find_group(groups, icon_index)
+        .with_context(|| format!("No icon {icon_index} in {pe_path:?}"))?;
+    build_group(index, id, group, icons)
+}
+
+/// Index, id and `GRPICONDIR` of the group a shortcut's icon index refers to
+fn find_group(groups: &ResourceTable, icon_index: i32) -> Option<(usize, IconResourceId, &[u8])> {
+    group_entries(groups).find(|(index, id, _)| match icon_index {
+        0.. => *index == icon_index as usize,
+        _ => *id == IconResourceId::Id(icon_index.unsigned_abs()),
+    })
+}
+
+fn parse(pe_path: &Path) -> Result<Image<'static>> {
+    Image::parse_file(pe_path).map_err(|e| anyhow!("Failed to parse {pe_path:?}: {e}"))
+}
+
+/// RT_GROUP_ICON and RT_ICON tables
+fn icon_tables<'a>(image: &'a Image) -> Option<(&'a ResourceTable, &'a ResourceTable)> {
+    let root = image.resource_directory()?.root();
+    let table = |resource_type: u16| {
+        root.get(ResourceEntryName::ID(resource_type as u32))
+            .and_then(ResourceEntry::as_table)
+    };
+    Some((table(RT_GROUP_ICON)?, table(RT_ICON)?))
+}
+
+/// Index, id and `GRPICONDIR` of every group
+fn group_entries(groups: &ResourceTable) -> impl Iterator<Item = (usize, IconResourceId, &[u8])> {
+    groups
+        .entries()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let id = match name {
+                ResourceEntryName::ID(id) => IconResourceId::Id(*id),
+                name => IconResourceId::Name(name.to_string().unwrap_or_default()),
+            };
+            Some((index, id, first_data(groups.get(name)?)?))
+        })
+}
+
+/// Data of the first language of a resource
+fn first_data(entry: &ResourceEntry) -> Option<&[u8]> {
+    match entry {
+        ResourceEntry::Data(data) => Some(data.data()),
+        ResourceEntry::Table(table) => table
+            .entries()
+            .first()
+            .and_then(|name| first_data(table.get(*name)?)),
+    }
+}
+
+/// Copy the RT_ICON frames of a group behind an `ICONDIR`
+fn build_group(
+    index: usize,
+    id: IconResourceId,
+    group: &[u8],
+    icons: &ResourceTable,
+) -> Result<PeIconGroup> {
+    ensure!(group.len() >= DIR_HEADER_SIZE, "Truncated icon group");
+    let count = u16::from_le_bytes([group[4], group[5]]) as usize;
+
+    let mut frames = Vec::new();
+    for n in 0..count {
+        let start = DIR_HEADER_SIZE + n * GROUP_ENTRY_SIZE;
+        let entry = group
+            .get(start..start + GROUP_ENTRY_SIZE)
+            .context("Truncated icon group")?;
+        let icon_id = u16::from_le_bytes([entry[12], entry[13]]);
+        match icons
+            .get(ResourceEntryName::ID(icon_id as u32))
+            .and_then(first_data)
+        {
+            Some(data) => frames.push((entry, data)),
+            None => warn!("Missing icon #{icon_id} of group {id}"),
+        }
+    }
+    ensure!(!frames.is_empty(), "Icon group {id} has no icons");
+
+    let mut ico = Vec::new();
+    ico.extend_from_slice(&[0, 0, 1, 0]);
+    ico.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+    let mut offset = DIR_HEADER_SIZE + ICO_ENTRY_SIZE * frames.len();
+    for (entry, data) in &frames {
+        // 宽、高、颜色数、保留、色彩平面、位深与GRPICONDIRENTRY相同
+        ico.extend_from_slice(&entry[..8]);
+        ico.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += data.len();
+    }
+    for (_, data) in &frames {
+        ico.extend_from_slice(data);
+    }
+
+    // 宽度0表示256
+    let sizes = frames
+        .iter()
+        .map(|(entry, _)| match entry[0] {
+            0 => 256,
+            width => width as u32,
+        })
+        .collect();
+
+    Ok(PeIconGroup {
+        index,
+        id,
+        sizes,
+        ico,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editpe::ResourceData;
+
+    fn data(bytes: Vec<u8>) -> ResourceEntry {
+        let mut data = ResourceData::default();
+        data.set_data(bytes);
+        ResourceEntry::Data(data)
+    }
+
+    /// Resources are stored per language, as in a real PE file
+    fn language_table(bytes: Vec<u8>) -> ResourceEntry {
+        let mut table = ResourceTable::default();
+        table.insert(ResourceEntryName::ID(1033), data(bytes));
+        ResourceEntry::Table(table)
+    }
+
+    /// `BITMAPINFOHEADER` of a 32-bit frame, only the header is parsed
+    fn frame(width: u8) -> Vec<u8> {
+        let width = match width {
+            0 => 256,
+            width => width as i32,
+        };
+        let mut header = vec![0; 40];
+        header[..4].copy_from_slice(&40u32.to_le_bytes());
+        header[4..8].copy_from_slice(&width.to_le_bytes());
+        header[8..12].copy_from_slice(&(width * 2).to_le_bytes());
+        header[12] = 1;
+        header[14] = 32;
+        header
+    }
+
+    /// `GRPICONDIR` of the frames `(width, RT_ICON id)`
+    fn group(frames: &[(u8, u16)]) -> Vec<u8> {
+        let mut group = vec![0, 0, 1, 0];
+        group.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+        for &(width, icon_id) in frames {
+            group.extend_from_slice(&[width, width, 0, 0, 1, 0, 32, 0]);
+            group.extend_from_slice(&(frame(width).len() as u32).to_le_bytes());
+            group.extend_from_slice(&icon_id.to_le_bytes());
+        }
+        group
+    }
+
+    /// Groups `#1`, `MAINICON`, `#5` and `#7`, the last one refers to no icon
+    fn tables() -> (ResourceTable, ResourceTable) {
+        let mut icons = ResourceTable::default();
+        for (icon_id, width) in [(1, 16), (2, 0), (3, 32), (4, 48)] {
+            icons.insert(ResourceEntryName::ID(icon_id), language_table(frame(width)));
+        }
+
+        let mut groups = ResourceTable::default();
+        groups.insert(
+            ResourceEntryName::ID(1),
+            language_table(group(&[(16, 1), (0, 2)])),
+        );
+        groups.insert(
+            ResourceEntryName::from_string("MAINICON"),
+            data(group(&[(48, 4)])),
+        );
+        // 缺失的图标被跳过
+        groups.insert(
+            ResourceEntryName::ID(5),
+            language_table(group(&[(32, 3), (24, 9)])),
+        );
+        groups.insert(ResourceEntryName::ID(7), language_table(group(&[(16, 9)])));
+        (groups, icons)
+    }
+
+    fn icon(
+        groups: &ResourceTable,
+        icons: &ResourceTable,
+        icon_index: i32,
+    ) -> Option<Result<PeIconGroup>> {
+        let (index, id, group) = find_group(groups, icon_index)?;
+        Some(build_group(index, id, group, icons))
+    }
+
+    #[test]
+    fn icon_index_selects_the_group_by_position() {
+        let (groups, icons) = tables();
+
+        let group = icon(&groups, &icons, 0).unwrap().unwrap();
+        assert_eq!((group.index, group.id), (0, IconResourceId::Id(1)));
+        assert_eq!(group.sizes, [16, 256]);
+        let ico = IcoFile::parse(&group.ico).unwrap();
+        let widths = ico.frames.iter().map(|f| f.width).collect::<Vec<_>>();
+        assert_eq!(widths, [16, 256]);
+
+        let group = icon(&groups, &icons, 1).unwrap().unwrap();
+        assert_eq!(group.id, IconResourceId::Name("MAINICON".to_owned()));
+        assert_eq!(group.sizes, [48]);
+    }
+
+    #[test]
+    fn negative_icon_index_is_a_resource_id() {
+        let (groups, icons) = tables();
+
+        let group = icon(&groups, &icons, -5).unwrap().unwrap();
+        assert_eq!((group.index, group.id), (2, IconResourceId::Id(5)));
+        assert_eq!(group.sizes, [32]);
+        assert_eq!(IcoFile::parse(&group.ico).unwrap().frames.len(), 1);
+
+        assert_eq!(icon(&groups, &icons, -1).unwrap().unwrap().index, 0);
+        assert!(icon(&groups, &icons, -2).is_none());
+    }
+
+    #[test]
+    fn missing_icons_are_errors() {
+        let (groups, icons) = tables();
+
+        assert!(icon(&groups, &icons, 4).is_none());
+        assert!(icon(&groups, &icons, i32::MAX).is_none());
+        assert!(icon(&groups, &icons, i32::MIN).is_none());
+        // 组内的图标均不存在
+        assert!(icon(&groups, &icons, 3).unwrap().is_err());
+
+        // 不是PE文件
+        assert!(pe_icon(Path::new("tests/fixtures/notepad.lnk"), 0).is_err());
+
+        let id = IconResourceId::Id(1);
+        assert!(build_group(0, id.clone(), &[0, 0, 1], &icons).is_err());
+        assert!(build_group(0, id, &group(&[(16, 1)])[..10], &icons).is_err());
+    }
+}
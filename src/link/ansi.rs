@@ -0,0 +1,63 @@
+// System ANSI code page - 系统 ANSI 代码页
+//
+// ANSI strings of .lnk files and .url files are written in the code page of the system
+// that created them (CP1252, GBK, Shift-JIS...), not in UTF-8
+
+/// Decode bytes of the ANSI code page, `None` when they can't be decoded reliably
+///
+/// Outside Windows the code page is unknown, only ASCII is decoded - 非 Windows 系统仅解码 ASCII
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    if bytes.is_ascii() {
+        return String::from_utf8(bytes.to_vec()).ok();
+    }
+    system::decode(bytes)
+}
+
+/// Encode into the ANSI code page, `None` when a character has no exact mapping
+pub fn encode(value: &str) -> Option<Vec<u8>> {
+    if value.is_ascii() {
+        return Some(value.as_bytes().to_vec());
+    }
+    system::encode(value)
+}
+
+#[cfg(windows)]
+mod system {
+    use windows::{
+        Win32::Globalization::{
+            CP_ACP, MB_ERR_INVALID_CHARS, MultiByteToWideChar, WideCharToMultiByte,
+        },
+        core::PCSTR,
+    };
+
+    pub fn decode(bytes: &[u8]) -> Option<String> {
+        let len = unsafe { MultiByteToWideChar(CP_ACP, MB_ERR_INVALID_CHARS, bytes, None) };
+        let mut wide = vec![0u16; usize::try_from(len).ok().filter(|&l| l > 0)?];
+        let len =
+            unsafe { MultiByteToWideChar(CP_ACP, MB_ERR_INVALID_CHARS, bytes, Some(&mut wide)) };
+        wide.truncate(usize::try_from(len).ok()?);
+        String::from_utf16(&wide).ok()
+    }
+
+    pub fn encode(value: &str) -> Option<Vec<u8>> {
+        let wide: Vec<u16> = value.encode_utf16().collect();
+        let len = unsafe { WideCharToMultiByte(CP_ACP, 0, &wide, None, PCSTR::null(), None) };
+        let mut bytes = vec![0u8; usize::try_from(len).ok().filter(|&l| l > 0)?];
+        let len =
+            unsafe { WideCharToMultiByte(CP_ACP, 0, &wide, Some(&mut bytes), PCSTR::null(), None) };
+        bytes.truncate(usize::try_from(len).ok()?);
+        // Best-fit and default characters don't survive the round trip - 拒绝近似替换的字符
+        (decode(&bytes).as_deref() == Some(value)).then_some(bytes)
+    }
+}
+
+#[cfg(not(windows))]
+mod system {
+    pub fn decode(_bytes: &[u8]) -> Option<String> {
+        None
+    }
+
+    pub fn encode(_value: &str) -> Option<Vec<u8>> {
+        None
+    }
+}
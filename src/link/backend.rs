@@ -0,0 +1,482 @@
+use super::{
+    create::NewShortcut,
+    desktop_entry::{DesktopEntry, join_exec},
+    edit::LinkChanges,
+    info::ManageLinkProp,
+    internet_shortcut::InternetShortcut,
+    journal::IconLocation,
+    list::LinkProp,
+    shell_link::{ShellLink, StringField},
+};
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow, ensure};
+use glob::glob;
+use log::*;
+
+/// A kind of shortcut file that LinkEcho can list and re-icon - 快捷方式类型
+pub trait ShortcutBackend: Sync {
+    /// File extension handled by this backend, without the dot
+    fn extension(&self) -> &'static str;
+
+    /// Every shortcut below `dir`, recursively
+    fn discover(&self, dir: &Path) -> Vec<PathBuf> {
+        let pattern = dir.join("**").join(format!("*.{}", self.extension()));
+        let Some(pattern) = pattern.to_str() else {
+            return Vec::new();
+        };
+        glob(pattern)
+            .inspect_err(|e| error!("Glob failed for {pattern}: {e}"))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .collect()
+    }
+
+    fn read(&self, path: &Path) -> Result<LinkProp>;
+
+    /// Icon file and index written in the shortcut, `None` when it shows the target's icon
+    fn icon_location(&self, path: &Path) -> Result<Option<(String, i32)>>;
+
+    /// An empty `icon_path` clears the icon location
+    fn set_icon(&self, path: &Path, icon_path: &str, icon_index: i32) -> Result<()>;
+
+    /// Whether the icon before the first change is kept in original_icons.json
+    fn uses_original_icons(&self) -> bool {
+        true
+    }
+
+    /// Put back the icon the shortcut had before LinkEcho changed it
+    ///
+    /// `original` is the location captured in original_icons.json, a shortcut without one
+    /// is left unchanged.
+    fn restore_icon(&self, link_prop: &LinkProp, original: Option<&IconLocation>) -> Result<()>;
+
+    /// Write a new shortcut file at `path`
+    fn create(&self, path: &Path, new_shortcut: &NewShortcut) -> Result<()>;
+
+    /// Write the fields changed in the properties panel, the others are left as they are
+    fn write_props(&self, path: &Path, changes: &LinkChanges) -> Result<()>;
+}
+
+/// Windows shell links (.lnk)
+pub struct LnkBackend;
+
+impl ShortcutBackend for LnkBackend {
+    fn extension(&self) -> &'static str {
+        "lnk"
+    }
+
+    fn read(&self, path: &Path) -> Result<LinkProp> {
+        ManageLinkProp::get_info(path)
+    }
+
+    fn icon_location(&self, path: &Path) -> Result<Option<(String, i32)>> {
+        Ok(ShellLink::open(path)?.icon_location())
+    }
+
+    fn set_icon(&self, path: &Path, icon_path: &str, icon_index: i32) -> Result<()> {
+        let mut shell_link = ShellLink::open(path)?;
+        shell_link.set_icon_location(icon_path, icon_index)?;
+        shell_link.save(path)
+    }
+
+    /// Write back the captured icon location, an empty one clears it
+    fn restore_icon(&self, link_prop: &LinkProp, original: Option<&IconLocation>) -> Result<()> {
+        let path = Path::new(&link_prop.path);
+        let Some(original) = original else {
+            warn!("No original icon of {path:?}, it is left unchanged");
+            return Ok(());
+        };
+        let mut shell_link = ShellLink::open(path)?;
+        shell_link.set_icon_location(&original.path, original.index)?;
+        shell_link.save(path)
+    }
+
+    fn create(&self, path: &Path, new_shortcut: &NewShortcut) -> Result<()> {
+        let mut shell_link = ShellLink::new(&new_shortcut.target_path);
+        shell_link.set_string(StringField::Arguments, Some(&new_shortcut.arguments))?;
+        shell_link.set_string(StringField::WorkingDir, Some(&new_shortcut.working_dir))?;
+        if !new_shortcut.icon_path.is_empty() {
+            shell_link.set_icon_location(&new_shortcut.icon_path, new_shortcut.icon_index)?;
+        }
+        shell_link.save(path)
+    }
+
+    fn write_props(&self, path: &Path, changes: &LinkChanges) -> Result<()> {
+        let mut shell_link = ShellLink::open(path)?;
+        for (field, value) in [
+            (StringField::Arguments, &changes.arguments),
+            (StringField::WorkingDir, &changes.working_dir),
+            (StringField::Name, &changes.description),
+        ] {
+            if let Some(value) = value {
+                shell_link.set_string(field, Some(value))?;
+            }
+        }
+        if let Some(hotkey) = changes.hotkey {
+            shell_link.set_hotkey(hotkey.to_u16());
+        }
+        if let Some(show_command) = changes.show_command {
+            shell_link.set_show_command(show_command.to_u32());
+        }
+        shell_link.save(path)
+    }
+}
+
+/// freedesktop launchers (.desktop)
+pub struct DesktopEntryBackend;
+
+impl ShortcutBackend for DesktopEntryBackend {
+    fn extension(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn read(&self, path: &Path) -> Result<LinkProp> {
+        DesktopEntry::get_info(path)
+    }
+
+    fn icon_location(&self, path: &Path) -> Result<Option<(String, i32)>> {
+        Ok(DesktopEntry::open(path)?
+            .icon()
+            .filter(|icon| !icon.is_empty())
+            .map(|icon| (icon, 0)))
+    }
+
+    fn set_icon(&self, path: &Path, icon_path: &str, _icon_index: i32) -> Result<()> {
+        let mut entry = DesktopEntry::open(path)?;
+        entry.set_icon(icon_path);
+        entry.save(path)
+    }
+
+    /// The original icon is kept in the entry itself
+    fn uses_original_icons(&self) -> bool {
+        false
+    }
+
+    fn restore_icon(&self, link_prop: &LinkProp, _original: Option<&IconLocation>) -> Result<()> {
+        let path = Path::new(&link_prop.path);
+        let mut entry = DesktopEntry::open(path)?;
+        if entry.restore_icon() {
+            entry.save(path)?;
+        }
+        Ok(())
+    }
+
+    fn create(&self, path: &Path, new_shortcut: &NewShortcut) -> Result<()> {
+        let exec = join_exec(&new_shortcut.target_path, &new_shortcut.arguments);
+        let mut entry = DesktopEntry::new(&new_shortcut.name, &exec);
+        if !new_shortcut.working_dir.is_empty() {
+            entry.set("Path", &new_shortcut.working_dir);
+        }
+        if !new_shortcut.icon_path.is_empty() {
+            entry.set("Icon", &new_shortcut.icon_path);
+        }
+        entry.save(path)
+    }
+
+    fn write_props(&self, path: &Path, changes: &LinkChanges) -> Result<()> {
+        ensure!(
+            changes.hotkey.is_none() && changes.show_command.is_none(),
+            "Desktop entries have no hotkey or run mode"
+        );
+        let mut entry = DesktopEntry::open(path)?;
+        if let Some(arguments) = &changes.arguments {
+            entry.set_arguments(arguments);
+        }
+        for (key, value) in [
+            ("Path", &changes.working_dir),
+            ("Comment", &changes.description),
+        ] {
+            match value.as_deref() {
+                Some("") => entry.remove(key),
+                Some(value) => entry.set(key, value),
+                None => {}
+            }
+        }
+        entry.save(path)
+    }
+}
+
+/// Internet shortcuts (.url)
+pub struct UrlBackend;
+
+impl ShortcutBackend for UrlBackend {
+    fn extension(&self) -> &'static str {
+        "url"
+    }
+
+    fn read(&self, path: &Path) -> Result<LinkProp> {
+        InternetShortcut::get_info(path)
+    }
+
+    fn icon_location(&self, path: &Path) -> Result<Option<(String, i32)>> {
+        Ok(InternetShortcut::open(path)?.icon_location())
+    }
+
+    fn set_icon(&self, path: &Path, icon_path: &str, icon_index: i32) -> Result<()> {
+        let mut shortcut = InternetShortcut::open(path)?;
+        shortcut.set_icon_location(icon_path, icon_index);
+        shortcut.save(path)
+    }
+
+    /// Write back the captured icon location, a shortcut LinkEcho never changed is left alone
+    fn restore_icon(&self, link_prop: &LinkProp, original: Option<&IconLocation>) -> Result<()> {
+        let path = Path::new(&link_prop.path);
+        let Some(original) = original else {
+            warn!("No original icon of {path:?}, it is left unchanged");
+            return Ok(());
+        };
+        let mut shortcut = InternetShortcut::open(path)?;
+        shortcut.set_icon_location(&original.path, original.index);
+        shortcut.save(path)
+    }
+
+    fn create(&self, path: &Path, new_shortcut: &NewShortcut) -> Result<()> {
+        let mut shortcut = InternetShortcut::new(&new_shortcut.target_path);
+        shortcut.set_icon_location(&new_shortcut.icon_path, new_shortcut.icon_index);
+        shortcut.save(path)
+    }
+
+    fn write_props(&self, path: &Path, changes: &LinkChanges) -> Result<()> {
+        ensure!(
+            changes.arguments.is_none() && changes.description.is_none(),
+            "Internet shortcuts have no arguments or description"
+        );
+        let mut shortcut = InternetShortcut::open(path)?;
+        if let Some(working_dir) = &changes.working_dir {
+            shortcut.set_working_dir(working_dir);
+        }
+        if let Some(hotkey) = changes.hotkey {
+            shortcut.set_hotkey(hotkey.to_u16());
+        }
+        if let Some(show_command) = changes.show_command {
+            shortcut.set_show_command(show_command.to_u32());
+        }
+        shortcut.save(path)
+    }
+}
+
+static BACKENDS: [&dyn ShortcutBackend; 3] = [&LnkBackend, &UrlBackend, &DesktopEntryBackend];
+
+pub fn backends() -> &'static [&'static dyn ShortcutBackend] {
+    &BACKENDS
+}
+
+pub fn backend_by_extension(ext: &str) -> Option<&'static dyn ShortcutBackend> {
+    backends()
+        .iter()
+        .find(|backend| backend.extension().eq_ignore_ascii_case(ext))
+        .copied()
+}
+
+/// Backend matching the shortcut's extension - 根据扩展名选择后端
+pub fn backend_for(path: &Path) -> Result<&'static dyn ShortcutBackend> {
+    backend_in(backends(), path)
+}
+
+/// Backend of `backends` matching the shortcut's extension
+pub fn backend_in<'a>(
+    backends: &[&'a dyn ShortcutBackend],
+    path: &Path,
+) -> Result<&'a dyn ShortcutBackend> {
+    let ext = path.extension().map(|ext| ext.to_string_lossy());
+    ext.and_then(|ext| {
+        backends
+            .iter()
+            .find(|backend| backend.extension().eq_ignore_ascii_case(&ext))
+            .copied()
+    })
+    .ok_or_else(|| anyhow!("Unsupported shortcut: {path:?}"))
+}
+
+/// Read every shortcut below `dirs` with the given backends, sorted by name
+pub fn collect(backends: &[&dyn ShortcutBackend], dirs: &[impl AsRef<Path>]) -> Vec<LinkProp> {
+    let mut link_vec = dirs
+        .iter()
+        .flat_map(|dir| {
+            backends.iter().flat_map(move |backend| {
+                backend
+                    .discover(dir.as_ref())
+                    .into_iter()
+                    .filter_map(|path| {
+                        backend
+                            .read(&path)
+                            .inspect_err(|e| error!("Failed to get info:\n{path:?}\n{e}"))
+                            .ok()
+                    })
+            })
+        })
+        .collect::<Vec<LinkProp>>();
+
+    sort_by_name(&mut link_vec);
+
+    link_vec
+}
+
+/// 首字母排序
+pub fn sort_by_name(link_vec: &mut [LinkProp]) {
+    link_vec.sort_by_key(|prop| prop.name.chars().next().map(|c| c.to_ascii_lowercase()));
+}
+
+/// Shortcuts kept in memory, so batches can be tested without shortcut files - 内存中的快捷方式
+#[cfg(test)]
+pub mod memory {
+    use super::*;
+
+    use std::{collections::HashMap, sync::Mutex};
+
+    type Icons = HashMap<PathBuf, Option<(String, i32)>>;
+
+    #[derive(Default)]
+    pub struct MemoryBackend {
+        /// Icon location of every shortcut, `None` shows the target's icon
+        icons: Mutex<Icons>,
+    }
+
+    impl MemoryBackend {
+        pub fn add(&self, path: impl Into<PathBuf>, icon: Option<(&str, i32)>) {
+            let icon = icon.map(|(icon_path, icon_index)| (icon_path.to_owned(), icon_index));
+            self.icons.lock().unwrap().insert(path.into(), icon);
+        }
+
+        fn write(&self, path: &Path, icon: Option<(String, i32)>) -> Result<()> {
+            let mut icons = self.icons.lock().unwrap();
+            let slot = icons
+                .get_mut(path)
+                .ok_or_else(|| anyhow!("Shortcut not found: {path:?}"))?;
+            *slot = icon;
+            Ok(())
+        }
+    }
+
+    impl ShortcutBackend for MemoryBackend {
+        fn extension(&self) -> &'static str {
+            "lnk"
+        }
+
+        fn discover(&self, dir: &Path) -> Vec<PathBuf> {
+            let mut paths = self
+                .icons
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|path| path.starts_with(dir))
+                .cloned()
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        }
+
+        fn read(&self, path: &Path) -> Result<LinkProp> {
+            let (icon_path, icon_index) = self.icon_location(path)?.unwrap_or_default();
+            Ok(LinkProp {
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().into_owned(),
+                icon_path,
+                icon_index: icon_index.to_string(),
+                ..LinkProp::default()
+            })
+        }
+
+        fn icon_location(&self, path: &Path) -> Result<Option<(String, i32)>> {
+            self.icons
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| anyhow!("Shortcut not found: {path:?}"))
+        }
+
+        fn set_icon(&self, path: &Path, icon_path: &str, icon_index: i32) -> Result<()> {
+            let icon = (!icon_path.is_empty()).then(|| (icon_path.to_owned(), icon_index));
+            self.write(path, icon)
+        }
+
+        fn restore_icon(
+            &self,
+            link_prop: &LinkProp,
+            original: Option<&IconLocation>,
+        ) -> Result<()> {
+            let Some(original) = original else {
+                return Ok(());
+            };
+            let original =
+                (!original.path.is_empty()).then(|| (original.path.clone(), original.index));
+            self.write(Path::new(&link_prop.path), original)
+        }
+
+        fn create(&self, path: &Path, _new_shortcut: &NewShortcut) -> Result<()> {
+            self.add(path, None);
+            Ok(())
+        }
+
+        fn write_props(&self, path: &Path, _changes: &LinkChanges) -> Result<()> {
+            self.icon_location(path).map(|_| ())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{memory::MemoryBackend, *};
+
+    /// Restore without a captured original keeps the icon, a captured one is written back
+    fn check_restore(backend: &dyn ShortcutBackend, link_path: &Path) {
+        let link_prop = LinkProp {
+            path: link_path.to_string_lossy().into_owned(),
+            ..LinkProp::default()
+        };
+        backend.set_icon(link_path, "/icons/app.ico", 0).unwrap();
+
+        backend.restore_icon(&link_prop, None).unwrap();
+        assert_eq!(
+            backend.icon_location(link_path).unwrap(),
+            Some(("/icons/app.ico".to_owned(), 0))
+        );
+
+        let original = IconLocation {
+            path: "/icons/original.ico".to_owned(),
+            index: 2,
+        };
+        backend.restore_icon(&link_prop, Some(&original)).unwrap();
+        assert_eq!(
+            backend.icon_location(link_path).unwrap(),
+            Some(("/icons/original.ico".to_owned(), 2))
+        );
+
+        // 原本没有图标
+        backend
+            .restore_icon(&link_prop, Some(&IconLocation::default()))
+            .unwrap();
+        assert_eq!(backend.icon_location(link_path).unwrap(), None);
+    }
+
+    #[test]
+    fn lnk_without_original_is_left_unchanged() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/notepad.lnk");
+        let link_path = std::env::temp_dir().join("linkecho-backend-restore.lnk");
+        std::fs::copy(&fixture, &link_path).unwrap();
+        check_restore(&LnkBackend, &link_path);
+    }
+
+    #[test]
+    fn url_without_original_is_left_unchanged() {
+        let link_path = std::env::temp_dir().join("linkecho-backend-restore.url");
+        InternetShortcut::new("https://example.com/")
+            .save(&link_path)
+            .unwrap();
+        check_restore(&UrlBackend, &link_path);
+    }
+
+    #[test]
+    fn memory_without_original_is_left_unchanged() {
+        let backend = MemoryBackend::default();
+        backend.add("/links/App.lnk", None);
+        check_restore(&backend, Path::new("/links/App.lnk"));
+    }
+}
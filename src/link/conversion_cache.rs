@@ -0,0 +1,173 @@
+use crate::{
+    image::{ico_profile::IcoProfile, icongen::conversion_params},
+    utils::ensure_local_app_folder_exists,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result, anyhow};
+use log::*;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::Xxh3;
+
+const CONVERSION_CACHE_FILE: &str = "conversion_cache.json";
+pub const ICONS_DIR: &str = "icons";
+
+/// A source as it was when it was hashed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub hash: String,
+    pub len: u64,
+    /// Milliseconds since the Unix epoch
+    pub modified: u64,
+    /// Profile the output was written with, entries older than profiles used the modern one
+    #[serde(default)]
+    pub profile: IcoProfile,
+    /// Per-size images the output was assembled from
+    #[serde(default)]
+    pub size_sources: Vec<SizeSourceStamp>,
+    pub output: PathBuf,
+}
+
+/// A per-size image as it was when it was hashed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeSourceStamp {
+    pub size: u32,
+    pub path: PathBuf,
+    pub len: u64,
+    /// Milliseconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// Converted icons keyed by a hash of the source bytes and the conversion parameters - 图标转换缓存
+///
+/// Maps each source path to its output `icons\{stem}-{hash}.ico`, so equally named sources of
+/// different packs don't collide and an edited source is converted again. A source is only
+/// hashed again when its size or modified time, or one of its per-size images, changed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConversionCache(HashMap<String, CacheEntry>);
+
+impl ConversionCache {
+    fn path() -> Result<PathBuf> {
+        Ok(ensure_local_app_folder_exists()?.join(CONVERSION_CACHE_FILE))
+    }
+
+    /// 路径不区分大小写
+    fn key(source: &Path) -> String {
+        source.to_string_lossy().to_lowercase()
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = ConversionCache::path()?;
+        if !path.is_file() {
+            return Ok(ConversionCache::default());
+        }
+        let data = std::fs::read(&path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = ConversionCache::path()?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// Forget the outputs that were deleted
+    pub fn prune(&mut self) {
+        self.0.retain(|_, entry| entry.output.is_file());
+    }
+
+    /// Output of `source` and its per-size images converted with `profile`
+    ///
+    /// A changed source, image or profile replaces the stale entry. The stale output is left to
+    /// the icon GC, shortcuts may still use it.
+    pub fn output_path(
+        &mut self,
+        source: &Path,
+        size_sources: &BTreeMap<u32, PathBuf>,
+        profile: IcoProfile,
+    ) -> Result<PathBuf> {
+        let metadata = std::fs::metadata(source)?;
+        let (len, modified) = (metadata.len(), modified_millis(&metadata));
+        let stamps = size_sources
+            .iter()
+            .map(|(&size, path)| {
+                let metadata = std::fs::metadata(path)?;
+                Ok(SizeSourceStamp {
+                    size,
+                    path: path.clone(),
+                    len: metadata.len(),
+                    modified: modified_millis(&metadata),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let key = ConversionCache::key(source);
+        if let Some(entry) = self.0.get(&key).filter(|entry| {
+            entry.len == len
+                && entry.modified == modified
+                && entry.profile == profile
+                && entry.size_sources == stamps
+        }) {
+            return Ok(entry.output.clone());
+        }
+
+        let hash = content_hash(source, size_sources, profile)?;
+        let stem = source
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .with_context(|| anyhow!("Failed to get icon name: {source:?}"))?;
+        let output = ensure_local_app_folder_exists()?
+            .join(ICONS_DIR)
+            .join(format!("{stem}-{hash}.ico"));
+
+        let entry = CacheEntry {
+            hash,
+            len,
+            modified,
+            profile,
+            size_sources: stamps,
+            output: output.clone(),
+        };
+        if let Some(stale) = self.0.insert(key, entry).filter(|e| e.output != output) {
+            debug!(
+                "Source changed: {source:?}\n{:?} -> {output:?}",
+                stale.output
+            );
+        }
+        Ok(output)
+    }
+}
+
+fn modified_millis(metadata: &Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+/// xxh3 of the source bytes, the per-size images and the conversion parameters, 16 hex digits
+fn content_hash(
+    source: &Path,
+    size_sources: &BTreeMap<u32, PathBuf>,
+    profile: IcoProfile,
+) -> Result<String> {
+    let data = std::fs::read(source).with_context(|| format!("Failed to read {source:?}"))?;
+    let mut hasher = Xxh3::new();
+    hasher.update(&data);
+    for (size, path) in size_sources {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read {path:?}"))?;
+        hasher.update(&size.to_le_bytes());
+        hasher.update(&data);
+    }
+    hasher.update(conversion_params(profile).as_bytes());
+    Ok(format!("{:016x}", hasher.digest()))
+}
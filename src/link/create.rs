@@ -0,0 +1,143 @@
+use super::{
+    backend::{ShortcutBackend, backend_by_extension, sort_by_name},
+    desktop_entry::data_home,
+    info::SystemLinkDirs,
+    list::{LinkList, ShortcutSource},
+    utils::process_icon,
+};
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use dioxus::signals::{Readable, Signal, Writable};
+use log::*;
+use rust_i18n::t;
+
+#[derive(Default, Clone, PartialEq)]
+pub struct NewShortcut {
+    pub name: String,
+    /// Program, folder or URL - 目标程序、文件夹或网址
+    pub target_path: String,
+    pub arguments: String,
+    pub working_dir: String,
+    /// Empty to use the target's icon
+    pub icon_path: String,
+    pub icon_index: i32,
+}
+
+impl NewShortcut {
+    pub fn is_url(&self) -> bool {
+        self.target_path.contains("://")
+    }
+
+    fn validate(&self) -> Result<()> {
+        let name = self.name.trim();
+        ensure!(!name.is_empty(), "The shortcut name is empty");
+        ensure!(
+            !name.contains(['\\', '/', ':', '*', '?', '"', '<', '>', '|']),
+            "The shortcut name contains invalid characters: {name}"
+        );
+
+        let target_path = self.target_path.trim();
+        ensure!(!target_path.is_empty(), "The shortcut target is empty");
+        ensure!(
+            self.is_url() || target_path.contains('%') || Path::new(target_path).exists(),
+            "The shortcut target does not exist: {target_path}"
+        );
+
+        ensure!(
+            self.icon_path.is_empty() || Path::new(&self.icon_path).is_file(),
+            "The icon does not exist: {}",
+            self.icon_path
+        );
+
+        Ok(())
+    }
+}
+
+impl ShortcutSource {
+    /// Folder that receives shortcuts created for this source - 新建快捷方式的保存目录
+    fn create_dir(&self) -> Result<PathBuf> {
+        let dir = match self {
+            ShortcutSource::Desktop => SystemLinkDirs::Desktop
+                .get_path()?
+                .into_iter()
+                .next()
+                .with_context(|| "Failed to get the Desktop path")?,
+            ShortcutSource::StartMenu => SystemLinkDirs::StartMenu
+                .get_path()?
+                .into_iter()
+                .next()
+                .with_context(|| "Failed to get the Start Menu path")?
+                .join("Programs"),
+            ShortcutSource::Applications => data_home()
+                .with_context(|| "Failed to get the data home")?
+                .join("applications"),
+            ShortcutSource::Other(path) => path.clone(),
+        };
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// .desktop for Applications, .url for web addresses, .lnk otherwise
+    fn create_backend(&self, new_shortcut: &NewShortcut) -> Result<&'static dyn ShortcutBackend> {
+        let ext = match self {
+            _ if new_shortcut.is_url() => "url",
+            ShortcutSource::Applications => "desktop",
+            _ => "lnk",
+        };
+        backend_by_extension(ext).ok_or_else(|| anyhow!("Unsupported shortcut: .{ext}"))
+    }
+}
+
+/// Write a new shortcut into the current source and select it in the list
+pub fn create_shortcut(
+    mut link_list: Signal<LinkList>,
+    new_shortcut: &NewShortcut,
+) -> Result<String> {
+    new_shortcut.validate()?;
+
+    let (dir, backend) = {
+        let link_list_read = link_list.read();
+        let source = &link_list_read.source;
+        (source.create_dir()?, source.create_backend(new_shortcut)?)
+    };
+
+    let link_name = new_shortcut.name.trim();
+    let link_path = dir.join(format!("{link_name}.{}", backend.extension()));
+    ensure!(
+        !link_path.exists(),
+        "The shortcut already exists: {link_path:?}"
+    );
+
+    // Windows 快捷方式只能使用 .ico/.exe/.dll 图标
+    let mut new_shortcut = new_shortcut.clone();
+    let icon_path = Path::new(&new_shortcut.icon_path);
+    let is_dll = icon_path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("dll"));
+    if backend.extension() != "desktop" && !new_shortcut.icon_path.is_empty() && !is_dll {
+        new_shortcut.icon_path = process_icon(icon_path)?.to_string_lossy().into_owned();
+    }
+
+    backend.create(&link_path, &new_shortcut)?;
+    let link_prop = backend.read(&link_path)?;
+
+    info!(
+        "{}:\n{}\n{}",
+        t!("SUCCESS_CREATE_SHORTCUT"),
+        link_prop.path,
+        link_prop.target_path
+    );
+
+    let mut link_list_write = link_list.write();
+    let link_path = link_prop.path.clone();
+    link_list_write.items.push(link_prop);
+    sort_by_name(&mut link_list_write.items);
+    link_list_write.state.select = link_list_write
+        .items
+        .iter()
+        .position(|prop| prop.path == link_path);
+
+    Ok(link_name.to_owned())
+}
@@ -0,0 +1,420 @@
+// Desktop Entry Specification
+// https://specifications.freedesktop.org/desktop-entry-spec/latest/
+//
+// 解析 Linux 启动器 (.desktop)，仅修改 [Desktop Entry] 组中的 Icon 键，其余内容原样保留
+
+use super::{
+    edit::ShowCommand,
+    ini::IniFile,
+    list::{LinkProp, Status},
+};
+use crate::image::base64::get_img_base64_by_path;
+
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+const MAIN_GROUP: &str = "Desktop Entry";
+/// LinkEcho 第一次修改图标时记录原始图标
+const ORIGINAL_ICON_KEY: &str = "X-LinkEcho-Original-Icon";
+
+pub struct DesktopEntry {
+    ini: IniFile,
+}
+
+impl DesktopEntry {
+    /// A new `Type=Application` launcher
+    pub fn new(name: &str, exec: &str) -> Self {
+        let mut entry = DesktopEntry {
+            ini: IniFile::new("\n", false),
+        };
+        entry.set("Type", "Application");
+        entry.set("Name", name);
+        entry.set("Exec", exec);
+        entry
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(DesktopEntry {
+            ini: IniFile::open(path, false)?,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.ini.save(path)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.ini.get(MAIN_GROUP, key)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.ini.set(MAIN_GROUP, key, value);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.ini.remove(MAIN_GROUP, key);
+    }
+
+    pub fn name(&self) -> Option<String> {
+        self.get("Name")
+    }
+
+    pub fn icon(&self) -> Option<String> {
+        self.get("Icon")
+    }
+
+    pub fn original_icon(&self) -> Option<String> {
+        self.get(ORIGINAL_ICON_KEY)
+    }
+
+    /// Program and arguments of the `Exec` key, field codes removed - 移除 %f %u 等字段代码
+    pub fn exec(&self) -> Option<(String, Vec<String>)> {
+        let mut args = split_exec(&self.get("Exec")?).into_iter();
+        let program = args.next()?;
+        Some((program, args.collect()))
+    }
+
+    /// Replace the arguments of `Exec`, file and URL field codes are kept - 保留 %f %u 等字段代码
+    pub fn set_arguments(&mut self, arguments: &str) {
+        let Some(exec) = self.get("Exec") else {
+            return;
+        };
+        let Some((program, _)) = self.exec() else {
+            return;
+        };
+        let field_codes = exec
+            .split_whitespace()
+            .filter(|a| matches!(*a, "%f" | "%F" | "%u" | "%U"))
+            .collect::<Vec<_>>();
+        let arguments = std::iter::once(arguments)
+            .chain(field_codes)
+            .filter(|a| !a.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.set("Exec", &join_exec(&program, &arguments));
+    }
+
+    pub fn set_icon(&mut self, icon: &str) {
+        if self.original_icon().is_none() {
+            let original = self.icon().unwrap_or_default();
+            self.set(ORIGINAL_ICON_KEY, &original);
+        }
+        match icon.is_empty() {
+            true => self.remove("Icon"),
+            false => self.set("Icon", icon),
+        }
+    }
+
+    /// Put back the icon recorded before the first change - 恢复第一次修改前的图标
+    pub fn restore_icon(&mut self) -> bool {
+        let Some(original) = self.original_icon() else {
+            return false;
+        };
+        if original.is_empty() {
+            self.remove("Icon");
+        } else {
+            self.set("Icon", &original);
+        }
+        self.remove(ORIGINAL_ICON_KEY);
+        true
+    }
+
+    pub fn get_info(path: &Path) -> Result<LinkProp> {
+        let entry = DesktopEntry::open(path)?;
+        let link_path = path.to_string_lossy().into_owned();
+
+        let link_name = entry
+            .name()
+            .or_else(|| path.file_stem().and_then(OsStr::to_str).map(str::to_owned))
+            .with_context(|| format!("Failed to get the desktop entry name {path:?}"))?;
+
+        let (link_target_path, link_arguments) = entry
+            .exec()
+            .map(|(program, args)| {
+                let program =
+                    find_program(&program).map_or(program, |p| p.to_string_lossy().into_owned());
+                (program, args.join(" "))
+            })
+            .unwrap_or_default();
+
+        let link_target_dir = entry
+            .get("Path")
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| {
+                Path::new(&link_target_path)
+                    .parent()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+
+        let link_target_ext = Path::new(&link_target_path)
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        let resolve = |icon: Option<String>| {
+            icon.and_then(|icon| resolve_icon(&icon))
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let link_icon_path = resolve(entry.icon());
+        let link_original_icon_path = match entry.original_icon() {
+            Some(original) => resolve(Some(original)),
+            None => link_icon_path.clone(),
+        };
+
+        let link_icon_status = match entry.original_icon() {
+            Some(original) if entry.icon().unwrap_or_default() != original => Status::Changed,
+            _ => Status::Unchanged,
+        };
+
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to get the desktop entry's metadata: {path:?}"))?;
+        let format_time = |time: std::io::Result<std::time::SystemTime>| {
+            time.map(|t| {
+                chrono::DateTime::<chrono::Local>::from(t)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default()
+        };
+
+        Ok(LinkProp {
+            name: link_name,
+            icon_base64: get_img_base64_by_path(&link_icon_path),
+            target_icon_base64: get_img_base64_by_path(&link_original_icon_path),
+            path: link_path,
+            status: link_icon_status,
+            target_ext: link_target_ext,
+            target_dir: link_target_dir,
+            working_dir: entry.get("Path").unwrap_or_default(),
+            target_path: link_target_path,
+            icon_path: link_icon_path,
+            icon_index: String::new(),
+            arguments: link_arguments,
+            description: entry.get("Comment").unwrap_or_default(),
+            hotkey: String::new(),
+            show_command: ShowCommand::Normal,
+            file_size: format!("{:.2} KB", metadata.len() as f64 / 1024.0),
+            created_at: format_time(metadata.created()),
+            updated_at: format_time(metadata.modified()),
+            accessed_at: format_time(metadata.accessed()),
+        })
+    }
+}
+
+/// Build an `Exec` value, quoting the program when needed - 程序路径含空格时加引号
+pub fn join_exec(program: &str, arguments: &str) -> String {
+    let program = match program.contains([' ', '"', '\\', '$', '`']) {
+        true => format!(
+            "\"{}\"",
+            program
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('$', "\\$")
+                .replace('`', "\\`")
+        ),
+        false => program.to_owned(),
+    };
+    match arguments.trim() {
+        "" => program,
+        arguments => format!("{program} {arguments}"),
+    }
+}
+
+/// Split the `Exec` value, honouring quotes and dropping field codes
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    let mut chars = exec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '%' => match chars.next() {
+                Some('%') => current.push('%'),
+                // %f %F %u %U %i %c %k ... 字段代码
+                Some(_) => {}
+                None => current.push('%'),
+            },
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg || !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if has_arg || !current.is_empty() {
+        args.push(current);
+    }
+
+    args.retain(|a| !a.is_empty());
+    args
+}
+
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.is_absolute() {
+        return Some(path.to_path_buf());
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|p| p.is_file())
+    })
+}
+
+/// Icon names are looked up in hicolor and pixmaps - 图标名称在 hicolor 与 pixmaps 中查找
+fn resolve_icon(icon: &str) -> Option<PathBuf> {
+    let path = Path::new(icon);
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let mut data_dirs: Vec<PathBuf> = data_home().into_iter().collect();
+    data_dirs.extend(
+        env::var("XDG_DATA_DIRS")
+            .unwrap_or("/usr/local/share:/usr/share".to_owned())
+            .split(':')
+            .map(PathBuf::from),
+    );
+
+    let sizes = ["scalable", "256x256", "128x128", "64x64", "48x48", "32x32"];
+    data_dirs
+        .iter()
+        .flat_map(|dir| {
+            sizes
+                .iter()
+                .map(move |size| dir.join("icons/hicolor").join(size).join("apps"))
+                .chain(std::iter::once(dir.join("pixmaps")))
+        })
+        .flat_map(|dir| ["svg", "png", "xpm"].map(|ext| dir.join(format!("{icon}.{ext}"))))
+        .find(|p| p.is_file())
+}
+
+pub fn data_home() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+}
+
+/// `~/.config`, only its autostart folder is used
+#[cfg(not(windows))]
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+}
+
+pub fn desktop_dir() -> Option<PathBuf> {
+    env::var_os("XDG_DESKTOP_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join("Desktop")))
+}
+
+/// `~/.local/share/applications` and `~/Desktop`
+pub fn application_dirs() -> Vec<PathBuf> {
+    data_home()
+        .map(|p| p.join("applications"))
+        .into_iter()
+        .chain(desktop_dir())
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(content: &str) -> DesktopEntry {
+        DesktopEntry {
+            ini: IniFile::parse(content, false),
+        }
+    }
+
+    const FIREFOX: &str = "[Desktop Entry]\n\
+        Type=Application\n\
+        Name=Firefox\n\
+        Exec=\"/opt/Mozilla Firefox/firefox\" --new-window %u\n\
+        Icon=firefox\n\
+        \n\
+        [Desktop Action new-private-window]\n\
+        Icon=firefox-private\n";
+
+    #[test]
+    fn exec_is_split_without_field_codes() {
+        let entry = entry(FIREFOX);
+
+        assert_eq!(
+            entry.exec(),
+            Some((
+                "/opt/Mozilla Firefox/firefox".to_owned(),
+                vec!["--new-window".to_owned()]
+            ))
+        );
+    }
+
+    #[test]
+    fn arguments_keep_field_codes() {
+        let mut entry = entry(FIREFOX);
+        entry.set_arguments("--private-window");
+
+        assert_eq!(
+            entry.get("Exec").as_deref(),
+            Some("\"/opt/Mozilla Firefox/firefox\" --private-window %u")
+        );
+    }
+
+    #[test]
+    fn icon_change_is_restored() {
+        let mut entry = entry(FIREFOX);
+        entry.set_icon("/home/me/icons/firefox.png");
+        entry.set_icon("/home/me/icons/firefox-dark.png");
+
+        assert_eq!(
+            entry.icon().as_deref(),
+            Some("/home/me/icons/firefox-dark.png")
+        );
+        assert_eq!(entry.original_icon().as_deref(), Some("firefox"));
+
+        assert!(entry.restore_icon());
+        assert_eq!(entry.icon().as_deref(), Some("firefox"));
+        assert_eq!(entry.original_icon(), None);
+        // Other groups are left alone - 其他组保持不变
+        assert_eq!(
+            entry
+                .ini
+                .get("Desktop Action new-private-window", "Icon")
+                .as_deref(),
+            Some("firefox-private")
+        );
+        assert!(!entry.restore_icon());
+    }
+
+    #[test]
+    fn program_with_spaces_is_quoted() {
+        assert_eq!(join_exec("/usr/bin/code", ""), "/usr/bin/code");
+        assert_eq!(
+            join_exec("/opt/My App/app", "--flag"),
+            "\"/opt/My App/app\" --flag"
+        );
+    }
+}
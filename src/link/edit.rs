@@ -0,0 +1,354 @@
+use super::{
+    backend::backend_for,
+    list::{LinkList, LinkProp},
+};
+
+use std::{fmt, path::Path, str::FromStr};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use dioxus::signals::{Readable, Signal, Writable};
+use log::*;
+use rust_i18n::t;
+
+// HotKeyFlags
+const HOTKEYF_SHIFT: u8 = 0x01;
+const HOTKEYF_CONTROL: u8 = 0x02;
+const HOTKEYF_ALT: u8 = 0x04;
+
+/// Shell link hotkey: low byte is the virtual key code, high byte the modifiers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HotKey {
+    pub key: u8,
+    pub modifiers: u8,
+}
+
+impl HotKey {
+    pub fn from_u16(value: u16) -> Self {
+        let [key, modifiers] = value.to_le_bytes();
+        HotKey { key, modifiers }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        u16::from_le_bytes([self.key, self.modifiers])
+    }
+
+    pub fn is_none(self) -> bool {
+        self.key == 0
+    }
+}
+
+fn key_name(key: u8) -> Option<String> {
+    match key {
+        b'0'..=b'9' | b'A'..=b'Z' => Some((key as char).to_string()),
+        0x70..=0x87 => Some(format!("F{}", key - 0x6F)),
+        0x90 => Some("NumLock".to_owned()),
+        0x91 => Some("ScrollLock".to_owned()),
+        _ => None,
+    }
+}
+
+fn key_code(name: &str) -> Option<u8> {
+    let upper = name.to_ascii_uppercase();
+    match upper.as_bytes() {
+        [c @ (b'0'..=b'9' | b'A'..=b'Z')] => Some(*c),
+        [b'F', n @ ..] if !n.is_empty() => std::str::from_utf8(n)
+            .ok()?
+            .parse::<u8>()
+            .ok()
+            .filter(|n| (1..=24).contains(n))
+            .map(|n| 0x6F + n),
+        _ => match upper.as_str() {
+            "NUMLOCK" => Some(0x90),
+            "SCROLLLOCK" => Some(0x91),
+            _ => None,
+        },
+    }
+}
+
+impl fmt::Display for HotKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_none() {
+            return Ok(());
+        }
+        for (flag, name) in [
+            (HOTKEYF_CONTROL, "Ctrl"),
+            (HOTKEYF_ALT, "Alt"),
+            (HOTKEYF_SHIFT, "Shift"),
+        ] {
+            if self.modifiers & flag != 0 {
+                write!(f, "{name}+")?;
+            }
+        }
+        match key_name(self.key) {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "{:#04X}", self.key),
+        }
+    }
+}
+
+/// `Ctrl+Alt+K`, an empty string clears the hotkey
+impl FromStr for HotKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(HotKey::default());
+        }
+
+        let mut hotkey = HotKey::default();
+        for part in s.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => hotkey.modifiers |= HOTKEYF_CONTROL,
+                "alt" => hotkey.modifiers |= HOTKEYF_ALT,
+                "shift" => hotkey.modifiers |= HOTKEYF_SHIFT,
+                _ => {
+                    ensure!(hotkey.key == 0, "The hotkey has more than one key: {s}");
+                    hotkey.key = key_code(part).with_context(|| format!("Unknown key: {part}"))?;
+                }
+            }
+        }
+
+        ensure!(hotkey.key != 0, "The hotkey has no key: {s}");
+        // Windows 只接受带 Ctrl 或 Alt 的快捷键
+        ensure!(
+            hotkey.modifiers & (HOTKEYF_CONTROL | HOTKEYF_ALT) != 0,
+            "The hotkey needs Ctrl or Alt: {s}"
+        );
+        Ok(hotkey)
+    }
+}
+
+/// ShowCommand of the shell link header - 运行方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShowCommand {
+    #[default]
+    Normal,
+    Maximized,
+    Minimized,
+    /// Any other SW_* value, kept as it is
+    Other(u32),
+}
+
+impl ShowCommand {
+    pub const ALL: [ShowCommand; 3] = [
+        ShowCommand::Normal,
+        ShowCommand::Maximized,
+        ShowCommand::Minimized,
+    ];
+
+    /// SW_SHOWNORMAL, SW_SHOWMAXIMIZED and SW_SHOWMINNOACTIVE, other values are kept raw
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => ShowCommand::Normal,
+            3 => ShowCommand::Maximized,
+            7 => ShowCommand::Minimized,
+            _ => ShowCommand::Other(value),
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            ShowCommand::Normal => 1,
+            ShowCommand::Maximized => 3,
+            ShowCommand::Minimized => 7,
+            ShowCommand::Other(value) => value,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            ShowCommand::Normal => t!("SHOW_NORMAL").into_owned(),
+            ShowCommand::Maximized => t!("SHOW_MAXIMIZED").into_owned(),
+            ShowCommand::Minimized => t!("SHOW_MINIMIZED").into_owned(),
+            ShowCommand::Other(value) => format!("SW {value}"),
+        }
+    }
+}
+
+/// Fields of a shortcut that can be edited in the properties panel - 可编辑的快捷方式属性
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LinkEdit {
+    pub arguments: String,
+    pub working_dir: String,
+    pub description: String,
+    pub hotkey: String,
+    pub show_command: ShowCommand,
+}
+
+/// Fields changed in the properties panel, `None` leaves the field of the file untouched - 修改过的属性
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LinkChanges {
+    pub arguments: Option<String>,
+    pub working_dir: Option<String>,
+    pub description: Option<String>,
+    pub hotkey: Option<HotKey>,
+    pub show_command: Option<ShowCommand>,
+}
+
+impl LinkEdit {
+    /// The working directory is the one written in the shortcut, not expanded
+    pub fn from_prop(link_prop: &LinkProp) -> Self {
+        LinkEdit {
+            arguments: link_prop.arguments.clone(),
+            working_dir: link_prop.working_dir.clone(),
+            description: link_prop.description.clone(),
+            hotkey: link_prop.hotkey.clone(),
+            show_command: link_prop.show_command,
+        }
+    }
+
+    /// Check the changed fields against the shortcut and the other shortcuts of the list
+    pub fn validate(&self, link_prop: &LinkProp, items: &[LinkProp]) -> Result<LinkChanges> {
+        let original = LinkEdit::from_prop(link_prop);
+        let changed =
+            |new: &str, old: &str| (new.trim() != old.trim()).then(|| new.trim().to_owned());
+
+        let working_dir = changed(&self.working_dir, &original.working_dir);
+        if let Some(working_dir) = &working_dir {
+            ensure!(
+                working_dir.is_empty()
+                    || working_dir.contains('%')
+                    || Path::new(working_dir).is_dir(),
+                "{}: {working_dir}",
+                t!("ERROR_WORKING_DIR_NOT_EXIST")
+            );
+        }
+
+        let hotkey = match changed(&self.hotkey, &original.hotkey) {
+            Some(hotkey) => Some(hotkey.parse::<HotKey>()?),
+            None => None,
+        };
+        if let Some(hotkey) = hotkey.filter(|hotkey| !hotkey.is_none()) {
+            let hotkey_text = hotkey.to_string();
+            if let Some(other) = items
+                .iter()
+                .find(|p| p.path != link_prop.path && p.hotkey == hotkey_text)
+            {
+                bail!(
+                    "{}: {hotkey_text} - {}",
+                    t!("ERROR_HOTKEY_CONFLICT"),
+                    other.name
+                );
+            }
+        }
+
+        Ok(LinkChanges {
+            arguments: changed(&self.arguments, &original.arguments),
+            working_dir,
+            description: changed(&self.description, &original.description),
+            hotkey,
+            show_command: (self.show_command != original.show_command).then_some(self.show_command),
+        })
+    }
+}
+
+/// Validate and write the edit to the selected shortcut, then re-read it
+pub fn edit_selected_shortcut(mut link_list: Signal<LinkList>, edit: &LinkEdit) -> Result<String> {
+    let index = link_list
+        .read()
+        .state
+        .select
+        .with_context(|| "LinkList's State prompt does not have a selection icon")?;
+
+    let link_prop = link_list.read().items[index].clone();
+    let changes = edit.validate(&link_prop, &link_list.read().items)?;
+
+    let link_path = Path::new(&link_prop.path);
+    let backend = backend_for(link_path)?;
+    backend
+        .write_props(link_path, &changes)
+        .map_err(|e| anyhow!("{}: {}\n{e}", t!("ERROR_EDIT_PROPERTIES"), link_prop.name))?;
+    let new_prop = backend.read(link_path)?;
+
+    info!("{}:\n{}", t!("SUCCESS_EDIT_PROPERTIES"), new_prop.path);
+
+    link_list.write().items[index] = new_prop;
+    Ok(link_prop.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::{
+        backend::{LnkBackend, ShortcutBackend},
+        shell_link::ShellLink,
+    };
+
+    fn link_prop() -> LinkProp {
+        LinkProp {
+            path: r"C:\Links\Far Cry 5.lnk".to_owned(),
+            target_dir: r"D:\Games\Far Cry 5\bin".to_owned(),
+            working_dir: r"%GAMES%\Far Cry 5\bin".to_owned(),
+            arguments: "-windowed".to_owned(),
+            hotkey: "Ctrl+Alt+F".to_owned(),
+            show_command: ShowCommand::Other(2),
+            ..LinkProp::default()
+        }
+    }
+
+    #[test]
+    fn show_command_keeps_other_values() {
+        for value in [1, 2, 3, 4, 7] {
+            assert_eq!(ShowCommand::from_u32(value).to_u32(), value);
+        }
+        assert_eq!(ShowCommand::from_u32(2), ShowCommand::Other(2));
+    }
+
+    #[test]
+    fn unchanged_edit_has_no_changes() {
+        let link_prop = link_prop();
+        let edit = LinkEdit::from_prop(&link_prop);
+
+        assert_eq!(edit.working_dir, link_prop.working_dir);
+        assert_eq!(
+            edit.validate(&link_prop, &[]).unwrap(),
+            LinkChanges::default()
+        );
+    }
+
+    #[test]
+    fn only_changed_fields_are_validated_and_returned() {
+        let link_prop = link_prop();
+        let items = [LinkProp {
+            path: r"C:\Links\Steam.lnk".to_owned(),
+            hotkey: "Ctrl+Alt+S".to_owned(),
+            ..LinkProp::default()
+        }];
+        let mut edit = LinkEdit::from_prop(&link_prop);
+        edit.description = " Ubisoft ".to_owned();
+
+        let changes = edit.validate(&link_prop, &items).unwrap();
+        assert_eq!(
+            changes,
+            LinkChanges {
+                description: Some("Ubisoft".to_owned()),
+                ..LinkChanges::default()
+            }
+        );
+
+        edit.hotkey = "Ctrl+Alt+S".to_owned();
+        assert!(edit.validate(&link_prop, &items).is_err());
+    }
+
+    #[test]
+    fn write_props_keeps_unchanged_fields() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/network_ansi.lnk");
+        let link_path = std::env::temp_dir().join("linkecho-edit-network_ansi.lnk");
+        std::fs::copy(&fixture, &link_path).unwrap();
+        let original = ShellLink::open(&link_path).unwrap();
+
+        let changes = LinkChanges {
+            description: Some("Ubisoft".to_owned()),
+            ..LinkChanges::default()
+        };
+        LnkBackend.write_props(&link_path, &changes).unwrap();
+
+        let edited = ShellLink::open(&link_path).unwrap();
+        assert_eq!(edited.description().as_deref(), Some("Ubisoft"));
+        assert_eq!(edited.working_dir(), original.working_dir());
+        assert_eq!(edited.arguments(), original.arguments());
+        assert_eq!(edited.hotkey(), original.hotkey());
+        assert_eq!(edited.show_command(), 2);
+    }
+}
@@ -0,0 +1,90 @@
+// 数据文件锁：多个 LinkEcho 进程同时修改 journal.json 等文件时，先读取最新内容再合并保存
+
+use std::{
+    fs::OpenOptions,
+    io::ErrorKind::AlreadyExists,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result, bail};
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// A lock this old was left by a process that crashed
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// `<file>.lock` next to a data file, removed when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Wait until no other process holds the lock of `file`
+    pub fn acquire(file: &Path) -> Result<Self> {
+        let mut path = file.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(FileLock { path }),
+                Err(e) if e.kind() == AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = std::fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() > TIMEOUT {
+                        bail!("Timed out waiting for {path:?}");
+                    }
+                    thread::sleep(RETRY_INTERVAL);
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {path:?}"));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = std::env::temp_dir().join("linkecho-file-lock");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("data.json");
+        let lock_path = dir.join("data.json.lock");
+        let _ = std::fs::remove_file(&lock_path);
+
+        let lock = FileLock::acquire(&file).unwrap();
+        assert!(lock_path.is_file());
+
+        let waiter = thread::spawn(move || FileLock::acquire(&file).map(drop));
+        thread::sleep(RETRY_INTERVAL * 4);
+        assert!(!waiter.is_finished());
+
+        drop(lock);
+        waiter.join().unwrap().unwrap();
+        assert!(!lock_path.exists());
+    }
+}
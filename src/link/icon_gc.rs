@@ -0,0 +1,172 @@
+use super::{
+    backend::backend_for,
+    conversion_cache::{ConversionCache, ICONS_DIR},
+    info::ManageLinkProp,
+    journal::Journal,
+    list::ShortcutSource,
+    original_icon::OriginalIcons,
+    snapshot::discover_shortcuts,
+};
+use crate::utils::ensure_local_app_folder_exists;
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::*;
+use rust_i18n::t;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+/// A file of the icons folder that no shortcut, journal entry or original icon refers to - 未使用的图标
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanIcon {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct GcSummary {
+    pub removed: usize,
+    pub freed: u64,
+}
+
+/// Every system source, plus the loaded folder if it is a custom one
+pub fn gc_sources(loaded: &ShortcutSource) -> Vec<ShortcutSource> {
+    let mut sources = vec![
+        ShortcutSource::Desktop,
+        ShortcutSource::StartMenu,
+        ShortcutSource::Applications,
+    ];
+    if let ShortcutSource::Other(path) = loaded {
+        sources.push(ShortcutSource::Other(path.clone()));
+    }
+    sources
+}
+
+/// 路径不区分大小写
+fn key(icon_path: &str) -> String {
+    ManageLinkProp::convert_env_to_path(icon_path)
+        .replace('/', "\\")
+        .to_lowercase()
+}
+
+/// Icon paths of every shortcut of `sources`, of every journal entry (undone ones included)
+/// and of original_icons.json
+///
+/// Shortcuts outside of `sources` that the journal or original_icons.json know are scanned too.
+/// A shortcut that can't be read fails the whole scan, its icon might be one of the orphans.
+fn referenced_icons(sources: &[ShortcutSource]) -> Result<HashSet<String>> {
+    let journal = Journal::load()?;
+    let original_icons = OriginalIcons::load()?;
+
+    let mut shortcuts = discover_shortcuts(sources);
+    let mut scanned = shortcuts
+        .iter()
+        .map(|(link_path, _)| key(&link_path.to_string_lossy()))
+        .collect::<HashSet<_>>();
+    for link_path in journal.link_paths().chain(original_icons.link_paths()) {
+        let link_path = Path::new(link_path);
+        if link_path.is_file() && scanned.insert(key(&link_path.to_string_lossy())) {
+            shortcuts.push((link_path.to_path_buf(), backend_for(link_path)?));
+        }
+    }
+
+    let mut referenced = HashSet::new();
+    for (link_path, backend) in shortcuts {
+        let location = backend
+            .icon_location(&link_path)
+            .with_context(|| format!("Failed to get the icon location: {link_path:?}"))?;
+        if let Some((icon_path, _)) = location {
+            referenced.insert(key(&icon_path));
+        }
+    }
+    referenced.extend(journal.icon_paths().map(key));
+    // 恢复原始图标时需要
+    referenced.extend(original_icons.icon_paths().map(key));
+    Ok(referenced)
+}
+
+/// Files of the icons folder that nothing refers to, by path
+pub fn find_orphan_icons(sources: &[ShortcutSource]) -> Result<Vec<OrphanIcon>> {
+    let icons_dir = ensure_local_app_folder_exists()?.join(ICONS_DIR);
+    if !icons_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let referenced = referenced_icons(sources)?;
+    let mut orphans = std::fs::read_dir(&icons_dir)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| OrphanIcon {
+                path: entry.path(),
+                size: metadata.len(),
+            })
+        })
+        .filter(|icon| !referenced.contains(&key(&icon.path.to_string_lossy())))
+        .collect::<Vec<_>>();
+    orphans.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(orphans)
+}
+
+/// Delete the orphans, after copying them into a zip archive if `archive_path` is given
+///
+/// The references are scanned again first, a shortcut may have taken one of the icons meanwhile.
+pub fn remove_orphan_icons(
+    sources: &[ShortcutSource],
+    orphans: &[OrphanIcon],
+    archive_path: Option<&Path>,
+) -> Result<GcSummary> {
+    let current = find_orphan_icons(sources)?;
+    let orphans = orphans
+        .iter()
+        .filter(|orphan| current.contains(orphan))
+        .collect::<Vec<_>>();
+
+    if let Some(archive_path) = archive_path {
+        archive_icons(archive_path, &orphans)?;
+    }
+
+    let mut summary = GcSummary::default();
+    for orphan in orphans {
+        match std::fs::remove_file(&orphan.path) {
+            Ok(()) => {
+                summary.removed += 1;
+                summary.freed += orphan.size;
+            }
+            Err(e) => error!("Failed to delete {:?}: {e}", orphan.path),
+        }
+    }
+
+    // 已删除的转换图标不再留在缓存中
+    let mut cache = ConversionCache::load()?;
+    cache.prune();
+    cache.save()?;
+
+    info!(
+        "{}: {} ({:.2} KB)",
+        t!("SUCCESS_REMOVE_ORPHAN_ICONS"),
+        summary.removed,
+        summary.freed as f64 / 1024.0
+    );
+    Ok(summary)
+}
+
+fn archive_icons(archive_path: &Path, icons: &[&OrphanIcon]) -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for icon in icons {
+        let file_name = icon
+            .path
+            .file_name()
+            .with_context(|| format!("Failed to get icon name: {:?}", icon.path))?;
+        zip.start_file(file_name.to_string_lossy(), options)?;
+        zip.write_all(&std::fs::read(&icon.path)?)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
@@ -1,22 +1,23 @@
 use super::{
+    edit::{HotKey, ShowCommand},
     list::{LinkProp, Status},
-    utils::initialize_com_and_create_shell_link,
+    shell_link::ShellLink,
 };
 use crate::image::base64::get_img_base64_by_path;
 
+#[cfg(windows)]
+use std::collections::HashMap;
 use std::{
-    collections::HashMap,
     env,
     ffi::OsStr,
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use chrono::{DateTime, Local};
-use glob::glob;
-use log::*;
-use winsafe::{IPersistFile, co, prelude::*};
+#[cfg(windows)]
+use winsafe::co;
 
 #[allow(unused)]
 pub enum SystemLinkDirs {
@@ -25,6 +26,7 @@ pub enum SystemLinkDirs {
     StartUp,
 }
 impl SystemLinkDirs {
+    #[cfg(windows)]
     pub fn get_path(&self) -> Result<Vec<PathBuf>> {
         // Get the GUID of the shortcut's folder
         let know_folder_id_vec = match self {
@@ -54,22 +56,30 @@ impl SystemLinkDirs {
 
         Ok(path)
     }
+
+    /// XDG folders: the desktop, the applications menu and autostart
+    #[cfg(not(windows))]
+    pub fn get_path(&self) -> Result<Vec<PathBuf>> {
+        use super::desktop_entry::{config_home, data_home, desktop_dir};
+
+        let path = match self {
+            SystemLinkDirs::Desktop => desktop_dir(),
+            SystemLinkDirs::StartMenu => data_home().map(|p| p.join("applications")),
+            SystemLinkDirs::StartUp => config_home().map(|p| p.join("autostart")),
+        };
+
+        Ok(path.into_iter().filter(|p| p.is_dir()).collect())
+    }
 }
 
 pub struct ManageLinkProp;
 impl ManageLinkProp {
-    pub fn get_info(
-        path_buf: &Path,
-        shell_link: &winsafe::IShellLink,
-        persist_file: &IPersistFile,
-    ) -> Result<LinkProp> {
+    pub fn get_info(path_buf: &Path) -> Result<LinkProp> {
         let link_path = path_buf
             .to_str()
             .with_context(|| format!("Invalid Unicode: {path_buf:?}"))?;
 
-        persist_file
-            .Load(link_path, co::STGM::READ)
-            .map_err(|e| anyhow!("Failed to load the link {path_buf:?} - {e}"))?;
+        let shell_link = ShellLink::open(path_buf)?;
 
         let link_name = path_buf
             .file_stem()
@@ -77,30 +87,108 @@ impl ManageLinkProp {
             .map(str::to_owned)
             .with_context(|| format!("Failed to get the lnk name {path_buf:?}"))?;
 
+        // 注意：提供的路径可能不存在（比如UWP、APP、未提供路径的lnk）
         let link_target_path = shell_link
-            .GetPath(
-                Some(&mut winsafe::WIN32_FIND_DATA::default()), // 注意：提供的路径可能不存在（比如UWP、APP、未提供路径的lnk）
-                co::SLGP::RAWPATH,                              // Absolute path - 绝对路径
-            )
+            .target_path()
             .as_deref()
             .map_or(String::new(), ManageLinkProp::convert_env_to_path);
 
         let link_target_dir = shell_link
-            .GetWorkingDirectory()
-            .ok()
+            .working_dir()
             .filter(|p| !p.is_empty())
             .as_deref()
             .map_or(
                 ManageLinkProp::get_parent_path(&link_target_path),
                 ManageLinkProp::convert_env_to_path,
             );
-            
-        let link_target_ext = if link_target_path.is_empty() {
+
+        let link_target_ext = ManageLinkProp::target_ext(&link_target_path);
+
+        // 不使用目标图标作为Base64是因为Base64内存占用大，性能差
+        let link_icon_base64 = get_img_base64_by_path(link_path);
+        let link_target_icon_base64 = get_img_base64_by_path(&link_target_path);
+
+        let (unconverted_icon_path, link_icon_path, link_icon_index) = shell_link
+            .icon_location()
+            .map(|(icon_path, icon_index)| {
+                let converted_icon_path = ManageLinkProp::convert_env_to_path(&icon_path);
+                match (Path::new(&icon_path).is_file(), icon_path.ends_with(".dll")) {
+                    (false, false) => (String::new(), String::new(), String::new()),
+                    _ => (icon_path, converted_icon_path, icon_index.to_string()),
+                }
+            })
+            .unwrap_or_default();
+
+        let link_icon_status = ManageLinkProp::icon_status(
+            &unconverted_icon_path,
+            &link_icon_path,
+            &link_target_path,
+            &link_target_dir,
+            &link_target_ext,
+        );
+
+        let link_arguments = shell_link.arguments().unwrap_or_default();
+        let link_description = shell_link.description().unwrap_or_default();
+        let link_hotkey = HotKey::from_u16(shell_link.hotkey()).to_string();
+        let link_show_command = ShowCommand::from_u32(shell_link.show_command());
+
+        fn format_system_time(time: SystemTime) -> String {
+            let datetime: DateTime<Local> = time.into();
+            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+        }
+
+        let metadata = std::fs::metadata(link_path)
+            .with_context(|| format!("Failed to get the shortcut's metadata: {link_name}"))?;
+
+        let link_file_size = format!("{:.2} KB", metadata.len() as f64 / 1024.0);
+
+        let link_created_at = metadata
+            .created()
+            .map(format_system_time)
+            .with_context(|| format!("Failed to get the shortcut's creation: {link_name}"))?;
+
+        let link_updated_at = metadata
+            .modified()
+            .map(format_system_time)
+            .with_context(|| format!("Failed to get the shortcut's updated time: {link_name}"))?;
+
+        let link_accessed_at = metadata
+            .accessed()
+            .map(format_system_time)
+            .with_context(|| format!("Failed to get the shortcut's accessed time: {link_name}"))?;
+
+        Ok(LinkProp {
+            name: link_name,
+            path: link_path.to_owned(),
+            status: link_icon_status,
+            target_ext: link_target_ext,
+            target_dir: link_target_dir,
+            working_dir: shell_link.working_dir().unwrap_or_default(),
+            target_path: link_target_path,
+            icon_base64: link_icon_base64,
+            target_icon_base64: link_target_icon_base64,
+            icon_path: link_icon_path,
+            icon_index: link_icon_index,
+            arguments: link_arguments,
+            description: link_description,
+            hotkey: link_hotkey,
+            show_command: link_show_command,
+            file_size: link_file_size,
+            created_at: link_created_at,
+            updated_at: link_updated_at,
+            accessed_at: link_accessed_at,
+        })
+    }
+
+    /// Kind of target shown in the list: the extension, a known system program, `uwp` or `app`
+    pub fn target_ext(link_target_path: &str) -> String {
+        if link_target_path.is_empty() {
             String::from("uwp|app")
         } else {
-            let link_target_file_name = Path::new(&link_target_path)
-                .file_name()
-                .and_then(OsStr::to_str)
+            // Also split Windows paths on other systems - 在其他系统上同样按反斜杠分割
+            let link_target_file_name = link_target_path
+                .rsplit(['\\', '/'])
+                .next()
                 .map(str::to_lowercase)
                 .unwrap_or_default();
 
@@ -127,7 +215,7 @@ impl ManageLinkProp {
                 "net.exe" => String::from("net"),           // 工作组连接安装程序
                 "netscan.exe" => String::from("netscan"),   // 网络扫描
                 _ => {
-                    let ext = Path::new(&link_target_path)
+                    let ext = Path::new(link_target_path)
                         .extension()
                         .and_then(OsStr::to_str)
                         .map(str::to_lowercase)
@@ -150,84 +238,31 @@ impl ManageLinkProp {
                     }
                 }
             }
-        };
-
-        // 不使用目标图标作为Base64是因为Base64内存占用大，性能差
-        let link_icon_base64 = get_img_base64_by_path(link_path);
-        let link_target_icon_base64 = get_img_base64_by_path(&link_target_path);
-
-        let (unconverted_icon_path, link_icon_path, link_icon_index) = shell_link
-            .GetIconLocation()
-            .map(|(icon_path, icon_index)| {
-                let converted_icon_path = ManageLinkProp::convert_env_to_path(&icon_path);
-                match (Path::new(&icon_path).is_file(), icon_path.ends_with(".dll")) {
-                    (false, false) => (String::new(), String::new(), String::new()),
-                    _ => (icon_path, converted_icon_path, icon_index.to_string()),
-                }
-            })
-            .with_context(|| format!("Failed get the shortcut icon location: {link_name}"))?;
-
-        let link_icon_dir = ManageLinkProp::get_parent_path(&link_icon_path);
+        }
+    }
 
-        let link_icon_status = if link_icon_path.is_empty() // unchanged、non-existent、inaccessible - 未更换图标、图标不存在、图标不可访问（UWP/APP）
+    /// A shortcut is `Changed` when its icon comes neither from the target nor from the system
+    pub fn icon_status(
+        unconverted_icon_path: &str,
+        link_icon_path: &str,
+        link_target_path: &str,
+        link_target_dir: &str,
+        link_target_ext: &str,
+    ) -> Status {
+        let link_icon_dir = ManageLinkProp::get_parent_path(link_icon_path);
+
+        if link_icon_path.is_empty() // unchanged、non-existent、inaccessible - 未更换图标、图标不存在、图标不可访问（UWP/APP）
             || link_icon_path == link_target_path // Icon from target file - 图标源于目标文件
             || link_target_ext == "app" // Windows Subsystem for Android - WSA应用
             || link_target_ext == "uwp" // Universal Windows Platform - UWP应用
             || unconverted_icon_path.starts_with("%")  // Icon From System icon - 系统图标 (%windir%/.../powershell.exe  ,  %windir%/.../imageres.dll)
-            || (link_icon_dir == link_target_dir && Path::new(&link_target_dir).is_dir())
+            || (link_icon_dir == link_target_dir && Path::new(link_target_dir).is_dir())
         // Icons come from the target file's (sub)dir - 图标来源于目标目录
         {
             Status::Unchanged
         } else {
             Status::Changed
-        };
-
-        let link_arguments = shell_link
-            .GetArguments()
-            .with_context(|| format!("Failed to get the shortcut's arguments: {link_name}"))?;
-
-        fn format_system_time(time: SystemTime) -> String {
-            let datetime: DateTime<Local> = time.into();
-            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
         }
-
-        let metadata = std::fs::metadata(link_path)
-            .with_context(|| format!("Failed to get the shortcut's metadata: {link_name}"))?;
-
-        let link_file_size = format!("{:.2} KB", metadata.len() as f64 / 1024.0);
-
-        let link_created_at = metadata
-            .created()
-            .map(format_system_time)
-            .with_context(|| format!("Failed to get the shortcut's creation: {link_name}"))?;
-
-        let link_updated_at = metadata
-            .modified()
-            .map(format_system_time)
-            .with_context(|| format!("Failed to get the shortcut's updated time: {link_name}"))?;
-
-        let link_accessed_at = metadata
-            .accessed()
-            .map(format_system_time)
-            .with_context(|| format!("Failed to get the shortcut's accessed time: {link_name}"))?;
-
-        Ok(LinkProp {
-            name: link_name,
-            path: link_path.to_owned(),
-            status: link_icon_status,
-            target_ext: link_target_ext,
-            target_dir: link_target_dir,
-            target_path: link_target_path,
-            icon_base64: link_icon_base64,
-            target_icon_base64: link_target_icon_base64,
-            icon_path: link_icon_path,
-            icon_index: link_icon_index,
-            arguments: link_arguments,
-            file_size: link_file_size,
-            created_at: link_created_at,
-            updated_at: link_updated_at,
-            accessed_at: link_accessed_at,
-        })
     }
 
     fn get_parent_path(path: &str) -> String {
@@ -238,6 +273,7 @@ impl ManageLinkProp {
             .unwrap_or_default()
     }
 
+    #[cfg(windows)]
     fn get_path_from_env(known_folder_id: Option<&co::KNOWNFOLDERID>, env: &str) -> String {
         if let Some(id) = known_folder_id {
             winsafe::SHGetKnownFolderPath(id, co::KF::NO_ALIAS, None).unwrap_or(
@@ -254,6 +290,7 @@ impl ManageLinkProp {
         }
     }
 
+    #[cfg(windows)]
     pub fn convert_env_to_path(env_path: &str) -> String {
         if !env_path.starts_with('%') {
             return env_path.to_owned();
@@ -364,30 +401,76 @@ impl ManageLinkProp {
             .unwrap_or(env_path.to_owned())
     }
 
-    pub fn collect(dirs_vec: &[impl AsRef<Path>]) -> Result<Vec<LinkProp>> {
-        let (shell_link, persist_file) = initialize_com_and_create_shell_link()?;
-
-        let mut link_vec = dirs_vec
-            .iter()
-            .filter_map(|p| p.as_ref().join("**\\*.lnk").to_str().map(str::to_owned))
-            // 使用 flat_map 合并两层迭代器
-            .flat_map(|pattern| {
-                glob(&pattern)
-                    .inspect_err(|e| error!("Glob failed for {pattern}: {e}"))
-                    .into_iter()
-                    .flatten() // 展开 Result 迭代器
-                    .filter_map(Result::ok)
-            })
-            .filter_map(|path| {
-                ManageLinkProp::get_info(&path, &shell_link, &persist_file)
-                    .inspect_err(|e| error!("Failed to get info:\n{path:?}\n{e}"))
-                    .ok()
-            })
-            .collect::<Vec<LinkProp>>();
+    /// Expand a leading `%NAME%` from the environment
+    #[cfg(not(windows))]
+    pub fn convert_env_to_path(env_path: &str) -> String {
+        env_path
+            .strip_prefix('%')
+            .and_then(|p| p.split_once('%'))
+            .and_then(|(name, rest)| env::var(name).ok().map(|root| format!("{root}{rest}")))
+            .unwrap_or(env_path.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_ext_detects_system_programs_and_apps() {
+        let target_ext = ManageLinkProp::target_ext;
+
+        assert_eq!(target_ext(r"D:\Games\Far Cry 5\bin\FarCry5.exe"), "exe");
+        assert_eq!(target_ext(r"C:\Windows\System32\CMD.EXE"), "cmd");
+        assert_eq!(target_ext(""), "uwp|app");
+        assert_eq!(
+            target_ext(r"C:\Users\Me\AppData\Local\Microsoft\WindowsApps\wt.exe"),
+            "uwp"
+        );
+        assert_eq!(
+            target_ext(r"C:\Users\Me\AppData\Local\Microsoft\WindowsSubsystemForAndroid\app.exe"),
+            "app"
+        );
+    }
 
-        // 首字母排序
-        link_vec.sort_by_key(|prop| prop.name.chars().next().map(|c| c.to_ascii_lowercase()));
+    #[test]
+    fn icon_status_of_fixture_corpus() {
+        let status = |name: &str| {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(name);
+            let shell_link = ShellLink::open(&path).unwrap();
+            let target_path = shell_link.target_path().unwrap_or_default();
+            let target_dir = shell_link.working_dir().unwrap_or_default();
+            let (icon_path, _) = shell_link.icon_location().unwrap_or_default();
+            ManageLinkProp::icon_status(
+                &icon_path,
+                &icon_path,
+                &target_path,
+                &target_dir,
+                &ManageLinkProp::target_ext(&target_path),
+            )
+        };
+
+        // System icon - 系统图标
+        assert!(status("notepad.lnk") == Status::Unchanged);
+        // Icon of the LinkEcho icons folder - 自定义图标
+        assert!(status("far_cry_5.lnk") == Status::Changed);
+        // No icon location - 无图标位置
+        assert!(status("uwp_calculator.lnk") == Status::Unchanged);
+    }
 
-        Ok(link_vec)
+    #[test]
+    fn icon_of_target_is_unchanged() {
+        let target = r"D:\Games\Far Cry 5\bin\FarCry5.exe";
+
+        assert!(
+            ManageLinkProp::icon_status(target, target, target, r"D:\Games", "exe")
+                == Status::Unchanged
+        );
+        assert!(
+            ManageLinkProp::icon_status(r"D:\Icons\a.ico", r"D:\Icons\a.ico", target, "", "uwp")
+                == Status::Unchanged
+        );
     }
 }
@@ -0,0 +1,250 @@
+// 行式 INI 编辑：只改动指定键所在的行，注释、空行、其他组原样保留
+
+use super::ansi;
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+
+const UTF8_BOM: &str = "\u{feff}";
+
+/// How the file was decoded, it is written back the same way
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextEncoding {
+    Utf8,
+    /// System ANSI code page, e.g. .url files saved by Windows
+    Ansi,
+    /// Unknown code page, one char per byte so untouched lines are written back byte for byte
+    Bytes,
+}
+
+pub struct IniFile {
+    lines: Vec<String>,
+    line_ending: &'static str,
+    encoding: TextEncoding,
+    bom: bool,
+    /// Windows INI keys are case-insensitive, Desktop Entry keys are not
+    ignore_case: bool,
+}
+
+impl IniFile {
+    pub fn new(line_ending: &'static str, ignore_case: bool) -> Self {
+        IniFile {
+            lines: Vec::new(),
+            line_ending,
+            encoding: TextEncoding::Utf8,
+            bom: false,
+            ignore_case,
+        }
+    }
+
+    /// UTF-8 first, then the system ANSI code page - 先按 UTF-8 解码，再按系统 ANSI 代码页
+    pub fn open(path: &Path, ignore_case: bool) -> Result<Self> {
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read the file: {path:?}"))?;
+        Ok(IniFile::decode(&data, ignore_case))
+    }
+
+    pub fn decode(data: &[u8], ignore_case: bool) -> Self {
+        let (content, encoding) = match std::str::from_utf8(data) {
+            Ok(content) => (content.to_owned(), TextEncoding::Utf8),
+            Err(_) => match ansi::decode(data) {
+                Some(content) => (content, TextEncoding::Ansi),
+                None => (
+                    data.iter().map(|&b| char::from(b)).collect(),
+                    TextEncoding::Bytes,
+                ),
+            },
+        };
+        let (content, bom) = match content.strip_prefix(UTF8_BOM) {
+            Some(content) => (content, true),
+            None => (content.as_str(), false),
+        };
+        IniFile {
+            encoding,
+            bom,
+            ..IniFile::parse(content, ignore_case)
+        }
+    }
+
+    /// Windows reads INI files in the ANSI code page, an ASCII file is written back in it too
+    pub fn ansi(mut self) -> Self {
+        if self.encoding == TextEncoding::Utf8
+            && !self.bom
+            && self.lines.iter().all(|l| l.is_ascii())
+        {
+            self.encoding = TextEncoding::Ansi;
+        }
+        self
+    }
+
+    pub fn parse(content: &str, ignore_case: bool) -> Self {
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        IniFile {
+            lines: content.lines().map(str::to_owned).collect(),
+            line_ending,
+            encoding: TextEncoding::Utf8,
+            bom: false,
+            ignore_case,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.encode()?)
+            .with_context(|| format!("Failed to write the file: {path:?}"))
+    }
+
+    /// Content in the encoding the file was read with, fails if a new value can't be written in it
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut content = match self.bom {
+            true => UTF8_BOM.to_owned(),
+            false => String::new(),
+        };
+        content.push_str(&self.lines.join(self.line_ending));
+        content.push_str(self.line_ending);
+        match self.encoding {
+            TextEncoding::Utf8 => Some(content.into_bytes()),
+            TextEncoding::Ansi => ansi::encode(&content),
+            TextEncoding::Bytes => content.chars().map(|c| u8::try_from(c).ok()).collect(),
+        }
+        .ok_or_else(|| anyhow!("The text can't be written in the file's code page"))
+    }
+
+    fn eq(&self, a: &str, b: &str) -> bool {
+        match self.ignore_case {
+            true => a.eq_ignore_ascii_case(b),
+            false => a == b,
+        }
+    }
+
+    /// Line range of the group, header excluded
+    fn group(&self, group: &str) -> Option<(usize, usize)> {
+        let start = self.lines.iter().position(|l| {
+            l.trim()
+                .strip_prefix('[')
+                .and_then(|l| l.strip_suffix(']'))
+                .is_some_and(|name| self.eq(name, group))
+        })? + 1;
+        let end = self.lines[start..]
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .map_or(self.lines.len(), |i| start + i);
+        Some((start, end))
+    }
+
+    fn find(&self, group: &str, key: &str) -> Option<usize> {
+        let (start, end) = self.group(group)?;
+        (start..end).find(|&i| {
+            self.lines[i]
+                .split_once('=')
+                .is_some_and(|(k, _)| self.eq(k.trim(), key))
+        })
+    }
+
+    pub fn get(&self, group: &str, key: &str) -> Option<String> {
+        self.find(group, key)
+            .and_then(|i| self.lines[i].split_once('='))
+            .map(|(_, v)| v.trim().to_owned())
+    }
+
+    pub fn set(&mut self, group: &str, key: &str, value: &str) {
+        let line = format!("{key}={value}");
+        match (self.find(group, key), self.group(group)) {
+            (Some(i), _) => self.lines[i] = line,
+            (None, Some((start, end))) => {
+                // 插入到组内最后一个非空行之后
+                let index = (start..end)
+                    .rev()
+                    .find(|&i| !self.lines[i].trim().is_empty())
+                    .map_or(start, |i| i + 1);
+                self.lines.insert(index, line);
+            }
+            (None, None) => {
+                self.lines.insert(0, format!("[{group}]"));
+                self.lines.insert(1, line);
+            }
+        }
+    }
+
+    /// Append an empty group at the end unless the file has it
+    pub fn add_group(&mut self, group: &str) {
+        if self.group(group).is_none() {
+            self.lines.push(format!("[{group}]"));
+        }
+    }
+
+    pub fn remove(&mut self, group: &str, key: &str) {
+        if let Some(i) = self.find(group, key) {
+            self.lines.remove(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSI_URL: &[u8] =
+        b"[InternetShortcut]\r\nURL=file:///C:/Caf\xe9/index.html\r\nIconIndex=0\r\n";
+
+    #[test]
+    fn ansi_file_is_written_back_unchanged() {
+        let ini = IniFile::decode(ANSI_URL, true);
+        assert_ne!(ini.encoding, TextEncoding::Utf8);
+        assert_eq!(ini.encode().unwrap(), ANSI_URL);
+    }
+
+    #[test]
+    fn ansi_file_keeps_untouched_lines() {
+        let mut ini = IniFile::decode(ANSI_URL, true);
+        ini.set("InternetShortcut", "IconFile", r"C:\Icons\cafe.ico");
+        let data = ini.encode().unwrap();
+        assert!(data.windows(b"Caf\xe9/".len()).any(|w| w == b"Caf\xe9/"));
+        assert!(data.ends_with(b"IconIndex=0\r\nIconFile=C:\\Icons\\cafe.ico\r\n"));
+    }
+
+    #[test]
+    fn utf8_bom_is_kept() {
+        let data = "\u{feff}[Desktop Entry]\nName=Café\n".as_bytes();
+        let mut ini = IniFile::decode(data, false);
+        assert_eq!(ini.get("Desktop Entry", "Name").as_deref(), Some("Café"));
+        ini.set("Desktop Entry", "Icon", "café");
+        assert_eq!(
+            ini.encode().unwrap(),
+            "\u{feff}[Desktop Entry]\nName=Café\nIcon=café\n".as_bytes()
+        );
+    }
+
+    #[test]
+    fn ascii_file_is_written_in_the_ansi_code_page() {
+        let data = b"[InternetShortcut]\r\nURL=https://example.com/\r\n";
+        assert_eq!(IniFile::decode(data, true).encoding, TextEncoding::Utf8);
+        let ini = IniFile::decode(data, true).ansi();
+        assert_eq!(ini.encoding, TextEncoding::Ansi);
+        assert_eq!(ini.encode().unwrap(), data);
+    }
+
+    #[test]
+    fn added_group_goes_last() {
+        let mut ini = IniFile::decode(b"[InternetShortcut]\r\nURL=a\r\n", true);
+        ini.add_group("InternetShortcut.W");
+        ini.add_group("InternetShortcut.W");
+        ini.set("InternetShortcut.W", "URL", "b");
+        assert_eq!(
+            ini.encode().unwrap(),
+            b"[InternetShortcut]\r\nURL=a\r\n[InternetShortcut.W]\r\nURL=b\r\n"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn unknown_code_page_rejects_wide_chars() {
+        let mut ini = IniFile::decode(ANSI_URL, true);
+        ini.set("InternetShortcut", "IconFile", r"C:\图标\app.ico");
+        assert!(ini.encode().is_err());
+    }
+}
@@ -0,0 +1,390 @@
+// Internet Shortcut (.url)
+//
+// [InternetShortcut]
+// URL=steam://rungameid/570
+// IconFile=C:\Program Files (x86)\Steam\steam\games\dota2.ico
+// IconIndex=0
+//
+// 仅修改 IconFile / IconIndex，其余内容原样保留，原始图标记录在 original_icons.json
+// 文件按 ANSI 代码页读取，非 ASCII 的值另以 UTF-7 写入 [InternetShortcut.W]，资源管理器优先读取该组
+
+use super::{
+    ansi,
+    edit::{HotKey, ShowCommand},
+    info::ManageLinkProp,
+    ini::IniFile,
+    journal::IconLocation,
+    list::{LinkProp, Status},
+    original_icon::OriginalIcons,
+};
+use crate::image::base64::{get_icon_base64_by_location, get_img_base64_by_path};
+
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{Context, Result};
+
+const MAIN_GROUP: &str = "InternetShortcut";
+/// Unicode values in UTF-7, they take precedence over the ANSI ones of `MAIN_GROUP`
+const UNICODE_GROUP: &str = "InternetShortcut.W";
+
+pub struct InternetShortcut {
+    ini: IniFile,
+}
+
+impl InternetShortcut {
+    pub fn new(url: &str) -> Self {
+        let mut shortcut = InternetShortcut {
+            ini: IniFile::new("\r\n", true).ansi(),
+        };
+        shortcut.set_text("URL", url);
+        shortcut
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(InternetShortcut {
+            ini: IniFile::open(path, true)?.ansi(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.ini.save(path)
+    }
+
+    /// Value of `UNICODE_GROUP` if it has the key, as Explorer reads it
+    fn text(&self, key: &str) -> Option<String> {
+        self.ini
+            .get(UNICODE_GROUP, key)
+            .and_then(|value| utf7_decode(&value))
+            .or_else(|| self.ini.get(MAIN_GROUP, key))
+    }
+
+    /// Write the value in the ANSI code page, and in `UNICODE_GROUP` if it isn't ASCII
+    ///
+    /// An ASCII value removes the old Unicode one, which would take precedence.
+    fn set_text(&mut self, key: &str, value: &str) {
+        let ansi_value = match ansi::encode(value) {
+            Some(_) => value.to_owned(),
+            None => value
+                .chars()
+                .map(|c| if c.is_ascii() { c } else { '?' })
+                .collect(),
+        };
+        self.ini.set(MAIN_GROUP, key, &ansi_value);
+        match value.is_ascii() {
+            true => self.ini.remove(UNICODE_GROUP, key),
+            false => {
+                self.ini.add_group(UNICODE_GROUP);
+                self.ini.set(UNICODE_GROUP, key, &utf7_encode(value));
+            }
+        }
+    }
+
+    fn remove_text(&mut self, key: &str) {
+        self.ini.remove(MAIN_GROUP, key);
+        self.ini.remove(UNICODE_GROUP, key);
+    }
+
+    pub fn url(&self) -> Option<String> {
+        self.text("URL")
+    }
+
+    pub fn working_dir(&self) -> Option<String> {
+        self.text("WorkingDirectory")
+    }
+
+    pub fn hotkey(&self) -> u16 {
+        self.ini
+            .get(MAIN_GROUP, "HotKey")
+            .and_then(|h| h.parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub fn show_command(&self) -> u32 {
+        self.ini
+            .get(MAIN_GROUP, "ShowCommand")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1)
+    }
+
+    pub fn set_working_dir(&mut self, working_dir: &str) {
+        match working_dir.is_empty() {
+            true => self.remove_text("WorkingDirectory"),
+            false => self.set_text("WorkingDirectory", working_dir),
+        }
+    }
+
+    pub fn set_hotkey(&mut self, hotkey: u16) {
+        self.set_or_remove("HotKey", &hotkey.to_string(), "0");
+    }
+
+    pub fn set_show_command(&mut self, show_command: u32) {
+        self.set_or_remove("ShowCommand", &show_command.to_string(), "1");
+    }
+
+    /// Default values are removed rather than written
+    fn set_or_remove(&mut self, key: &str, value: &str, default: &str) {
+        match value == default {
+            true => self.ini.remove(MAIN_GROUP, key),
+            false => self.ini.set(MAIN_GROUP, key, value),
+        }
+    }
+
+    /// `IconFile` and `IconIndex`, the index defaults to 0
+    pub fn icon_location(&self) -> Option<(String, i32)> {
+        let icon_file = self.text("IconFile").filter(|f| !f.is_empty())?;
+        let icon_index = self
+            .ini
+            .get(MAIN_GROUP, "IconIndex")
+            .and_then(|i| i.parse().ok())
+            .unwrap_or(0);
+        Some((icon_file, icon_index))
+    }
+
+    /// Write `IconFile`/`IconIndex`, an empty `icon_file` removes both
+    pub fn set_icon_location(&mut self, icon_file: &str, icon_index: i32) {
+        if icon_file.is_empty() {
+            self.remove_text("IconFile");
+            self.ini.remove(MAIN_GROUP, "IconIndex");
+        } else {
+            self.set_text("IconFile", icon_file);
+            self.ini
+                .set(MAIN_GROUP, "IconIndex", &icon_index.to_string());
+        }
+    }
+
+    pub fn get_info(path: &Path) -> Result<LinkProp> {
+        let shortcut = InternetShortcut::open(path)?;
+        let link_path = path.to_string_lossy().into_owned();
+
+        let link_name = path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .map(str::to_owned)
+            .with_context(|| format!("Failed to get the url name {path:?}"))?;
+
+        let link_url = shortcut.url().unwrap_or_default();
+
+        let (link_icon_path, link_icon_index) = shortcut
+            .icon_location()
+            .map(|(icon_file, icon_index)| {
+                (
+                    ManageLinkProp::convert_env_to_path(&icon_file),
+                    icon_index.to_string(),
+                )
+            })
+            .unwrap_or_default();
+        let original = OriginalIcons::load()?.get(path).cloned();
+        let link_original_icon_path = match &original {
+            Some(original) => ManageLinkProp::convert_env_to_path(&original.path),
+            None => link_icon_path.clone(),
+        };
+
+        let icon_location = shortcut
+            .icon_location()
+            .map(|(path, index)| IconLocation { path, index })
+            .unwrap_or_default();
+        let link_icon_status = match original.is_some_and(|original| original != icon_location) {
+            true => Status::Changed,
+            false => Status::Unchanged,
+        };
+
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to get the url's metadata: {path:?}"))?;
+        let format_time = |time: std::io::Result<std::time::SystemTime>| {
+            time.map(|t| {
+                chrono::DateTime::<chrono::Local>::from(t)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default()
+        };
+
+        Ok(LinkProp {
+            name: link_name,
+            icon_base64: get_icon_base64_by_location(
+                &link_icon_path,
+                link_icon_index.parse().unwrap_or(0),
+            ),
+            target_icon_base64: get_img_base64_by_path(&link_original_icon_path),
+            path: link_path,
+            status: link_icon_status,
+            target_ext: String::from("url"),
+            target_dir: shortcut
+                .working_dir()
+                .map(|dir| ManageLinkProp::convert_env_to_path(&dir))
+                .unwrap_or_default(),
+            working_dir: shortcut.working_dir().unwrap_or_default(),
+            target_path: link_url,
+            icon_path: link_icon_path,
+            icon_index: link_icon_index,
+            arguments: String::new(),
+            description: String::new(),
+            hotkey: HotKey::from_u16(shortcut.hotkey()).to_string(),
+            show_command: ShowCommand::from_u32(shortcut.show_command()),
+            file_size: format!("{:.2} KB", metadata.len() as f64 / 1024.0),
+            created_at: format_time(metadata.created()),
+            updated_at: format_time(metadata.modified()),
+            accessed_at: format_time(metadata.accessed()),
+        })
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Characters written as they are, RFC 2152 sets D and O without `\` and `~`
+fn is_utf7_direct(c: char) -> bool {
+    c.is_ascii_alphanumeric() || " \t\r\n'(),-./:?!\"#$%&*;<=>@[]^_`{|}".contains(c)
+}
+
+/// UTF-7 (RFC 2152), each run of other characters is closed with '-'
+fn utf7_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    let mut run: Vec<u16> = Vec::new();
+    let flush = |run: &mut Vec<u16>, encoded: &mut String| {
+        if run.is_empty() {
+            return;
+        }
+        let bytes = run.drain(..).flat_map(u16::to_be_bytes).collect::<Vec<_>>();
+        encoded.push('+');
+        for chunk in bytes.chunks(3) {
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |bits, (i, &b)| bits | u32::from(b) << (16 - 8 * i));
+            let chars = (chunk.len() * 8).div_ceil(6);
+            for i in 0..chars {
+                encoded.push(char::from(BASE64[(bits >> (18 - 6 * i) & 0x3f) as usize]));
+            }
+        }
+        encoded.push('-');
+    };
+    for c in value.chars() {
+        match c {
+            '+' => {
+                flush(&mut run, &mut encoded);
+                encoded.push_str("+-");
+            }
+            c if is_utf7_direct(c) => {
+                flush(&mut run, &mut encoded);
+                encoded.push(c);
+            }
+            c => run.extend(c.encode_utf16(&mut [0; 2]).iter()),
+        }
+    }
+    flush(&mut run, &mut encoded);
+    encoded
+}
+
+/// `None` when the value isn't valid UTF-7
+fn utf7_decode(value: &str) -> Option<String> {
+    let mut units: Vec<u16> = Vec::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '+' {
+            units.push(u16::try_from(u32::from(c)).ok()?);
+            continue;
+        }
+        if chars.next_if_eq(&'-').is_some() {
+            units.push(u16::from(b'+'));
+            continue;
+        }
+
+        let mut bits = 0u32;
+        let mut bit_count = 0;
+        while let Some(sextet) = chars
+            .peek()
+            .and_then(|&c| BASE64.iter().position(|&b| char::from(b) == c))
+        {
+            chars.next();
+            bits = bits << 6 | sextet as u32;
+            bit_count += 6;
+            if bit_count >= 16 {
+                bit_count -= 16;
+                units.push((bits >> bit_count) as u16);
+                bits &= (1 << bit_count) - 1;
+            }
+        }
+        chars.next_if_eq(&'-');
+    }
+    String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNICODE_URL: &[u8] = b"[InternetShortcut]\r\n\
+        URL=https://example.com/\r\n\
+        IconFile=C:\\Users\\??\\icon.ico\r\n\
+        IconIndex=0\r\n\
+        [InternetShortcut.W]\r\n\
+        IconFile=C:+AFw-Users+AFxfIE4JAFw-icon.ico\r\n";
+
+    #[test]
+    fn utf7_round_trip() {
+        for value in [
+            r"C:\Users\张三\AppData\Local\LinkEcho\icons\app.ico",
+            "a+b~c",
+            "Café 😀",
+            "plain",
+        ] {
+            assert_eq!(utf7_decode(&utf7_encode(value)).as_deref(), Some(value));
+        }
+        assert_eq!(utf7_encode("张三"), "+XyBOCQ-");
+        assert_eq!(utf7_decode("A+ImIDkQ.").as_deref(), Some("A≢Α."));
+    }
+
+    #[test]
+    fn unicode_icon_file_is_written_to_both_groups() {
+        let mut shortcut = InternetShortcut::new("https://example.com/");
+        let icon_file = r"C:\Users\张三\icons\app.ico";
+        shortcut.set_icon_location(icon_file, 0);
+
+        assert_eq!(shortcut.icon_location(), Some((icon_file.to_owned(), 0)));
+        assert_eq!(
+            shortcut.ini.get(UNICODE_GROUP, "IconFile"),
+            Some(utf7_encode(icon_file))
+        );
+        // ANSI 组中无法表示的字符为 '?'，文件仍可写入
+        let data = shortcut.ini.encode().unwrap();
+        let reopened = InternetShortcut {
+            ini: IniFile::decode(&data, true).ansi(),
+        };
+        assert_eq!(reopened.icon_location(), Some((icon_file.to_owned(), 0)));
+    }
+
+    #[test]
+    fn unicode_icon_file_is_updated() {
+        let mut shortcut = InternetShortcut {
+            ini: IniFile::decode(UNICODE_URL, true).ansi(),
+        };
+        assert_eq!(
+            shortcut.icon_location(),
+            Some((r"C:\Users\张三\icon.ico".to_owned(), 0))
+        );
+
+        let icon_file = r"C:\Users\李四\icon.ico";
+        shortcut.set_icon_location(icon_file, 1);
+        assert_eq!(shortcut.icon_location(), Some((icon_file.to_owned(), 1)));
+        assert_eq!(
+            shortcut.ini.get(UNICODE_GROUP, "IconFile"),
+            Some(utf7_encode(icon_file))
+        );
+    }
+
+    #[test]
+    fn unicode_icon_file_is_removed() {
+        let mut shortcut = InternetShortcut {
+            ini: IniFile::decode(UNICODE_URL, true).ansi(),
+        };
+        shortcut.set_icon_location(r"C:\Icons\app.ico", 0);
+        assert_eq!(shortcut.ini.get(UNICODE_GROUP, "IconFile"), None);
+        assert_eq!(
+            shortcut.icon_location(),
+            Some((r"C:\Icons\app.ico".to_owned(), 0))
+        );
+
+        shortcut.set_icon_location("", 0);
+        assert_eq!(shortcut.icon_location(), None);
+    }
+}
@@ -0,0 +1,641 @@
+use super::{
+    backend::{ShortcutBackend, backend_in, backends},
+    file_lock::FileLock,
+    list::{LinkList, LinkProp},
+    original_icon::{ORIGINAL_ICONS_FILE, OriginalIcons},
+};
+use crate::utils::ensure_local_app_folder_exists;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use dioxus::signals::{Readable, Signal, Writable};
+use log::*;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+
+pub const JOURNAL_FILE: &str = "journal.json";
+/// 超出后丢弃最早的记录
+const MAX_ENTRIES: usize = 5000;
+
+/// Icon file and index, an empty path means the shortcut shows its target's icon
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IconLocation {
+    pub path: String,
+    pub index: i32,
+}
+
+impl IconLocation {
+    fn of(backend: &dyn ShortcutBackend, link_path: &Path) -> Result<Self> {
+        Ok(backend
+            .icon_location(link_path)?
+            .map(|(path, index)| IconLocation { path, index })
+            .unwrap_or_default())
+    }
+}
+
+/// One icon change of one shortcut - 一条图标修改记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub link_path: String,
+    pub old: IconLocation,
+    pub new: IconLocation,
+    pub timestamp: String,
+    /// Changes made by one applied match plan, restore or import share the batch id
+    pub batch_id: u64,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// Persistent history of icon changes, saved as `journal.json` in the local app folder
+///
+/// The entries of a shortcut are in chronological order: the applied ones first,
+/// then the undone ones that can be redone.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    next_batch_id: u64,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    fn path() -> Result<PathBuf> {
+        Ok(ensure_local_app_folder_exists()?.join(JOURNAL_FILE))
+    }
+
+    pub fn load() -> Result<Self> {
+        Journal::open(&Journal::path()?)
+    }
+
+    /// Journal saved at `path`, empty if the file doesn't exist yet
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Journal::default());
+        }
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    /// Write to a temporary file first so an interrupted save keeps the old journal
+    fn save(&self, path: &Path) -> Result<()> {
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Reload, change and save the journal under its lock, so entries of other processes are kept
+    pub fn update<T>(path: &Path, f: impl FnOnce(&mut Journal) -> Result<T>) -> Result<T> {
+        let _lock = FileLock::acquire(path)?;
+        let mut journal = Journal::open(path)?;
+        let value = f(&mut journal)?;
+        journal.save(path)?;
+        Ok(value)
+    }
+
+    /// Icon paths on both sides of every entry, an undone change can still be redone
+    pub fn icon_paths(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .flat_map(|entry| [entry.old.path.as_str(), entry.new.path.as_str()])
+            .filter(|path| !path.is_empty())
+    }
+
+    /// Shortcuts with entries, a shortcut may be listed more than once
+    pub fn link_paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.link_path.as_str())
+    }
+
+    /// Applied changes of a shortcut, latest first
+    pub fn applied_changes<'a>(
+        &'a self,
+        link_path: &'a str,
+    ) -> impl Iterator<Item = &'a JournalEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |e| !e.undone && e.link_path == link_path)
+    }
+
+    /// Start a batch, every change made through it is undone and redone together
+    pub fn begin<'a>(backends: &'a [&'a dyn ShortcutBackend]) -> Result<Batch<'a>> {
+        Ok(Batch::new(ensure_local_app_folder_exists()?, backends))
+    }
+
+    fn record(&mut self, entry: JournalEntry) {
+        // 新的修改使该快捷方式已撤销的记录无法重做
+        self.entries
+            .retain(|e| !(e.undone && e.link_path == entry.link_path));
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+    }
+
+    /// Write the old (undo) or new (redo) icon location of an entry
+    fn apply(
+        &mut self,
+        backends: &[&dyn ShortcutBackend],
+        index: usize,
+        undo: bool,
+    ) -> Result<String> {
+        let entry = &self.entries[index];
+        let location = if undo { &entry.old } else { &entry.new };
+        let link_path = Path::new(&entry.link_path);
+        backend_in(backends, link_path)?.set_icon(link_path, &location.path, location.index)?;
+
+        let action = match undo {
+            true => t!("SUCCESS_UNDO"),
+            false => t!("SUCCESS_REDO"),
+        };
+        info!("{action}:\n{}\n{}", entry.link_path, location.path);
+
+        let link_path = entry.link_path.clone();
+        self.entries[index].undone = undo;
+        Ok(link_path)
+    }
+
+    /// Undo the last applied change of a shortcut, returns the changed shortcut
+    pub fn undo_shortcut(
+        &mut self,
+        backends: &[&dyn ShortcutBackend],
+        link_path: &str,
+    ) -> Result<Option<String>> {
+        let Some(index) = self
+            .entries
+            .iter()
+            .rposition(|e| !e.undone && e.link_path == link_path)
+        else {
+            return Ok(None);
+        };
+        let link_path = self.apply(backends, index, true)?;
+        Ok(Some(link_path))
+    }
+
+    /// Redo the first undone change of a shortcut, returns the changed shortcut
+    pub fn redo_shortcut(
+        &mut self,
+        backends: &[&dyn ShortcutBackend],
+        link_path: &str,
+    ) -> Result<Option<String>> {
+        let Some(index) = self
+            .entries
+            .iter()
+            .position(|e| e.undone && e.link_path == link_path)
+        else {
+            return Ok(None);
+        };
+        let link_path = self.apply(backends, index, false)?;
+        Ok(Some(link_path))
+    }
+
+    /// Undo the latest batch that still has applied changes, returns the changed shortcuts
+    pub fn undo_batch(&mut self, backends: &[&dyn ShortcutBackend]) -> Result<Vec<String>> {
+        let Some(batch_id) = self
+            .entries
+            .iter()
+            .filter(|e| !e.undone)
+            .map(|e| e.batch_id)
+            .max()
+        else {
+            return Ok(Vec::new());
+        };
+        let indexes = (0..self.entries.len())
+            .rev()
+            .filter(|&i| !self.entries[i].undone && self.entries[i].batch_id == batch_id)
+            .collect::<Vec<_>>();
+        self.apply_batch(backends, indexes, true)
+    }
+
+    /// Redo the earliest batch that has undone changes, returns the changed shortcuts
+    pub fn redo_batch(&mut self, backends: &[&dyn ShortcutBackend]) -> Result<Vec<String>> {
+        let Some(batch_id) = self
+            .entries
+            .iter()
+            .filter(|e| e.undone)
+            .map(|e| e.batch_id)
+            .min()
+        else {
+            return Ok(Vec::new());
+        };
+        let indexes = (0..self.entries.len())
+            .filter(|&i| self.entries[i].undone && self.entries[i].batch_id == batch_id)
+            .collect::<Vec<_>>();
+        self.apply_batch(backends, indexes, false)
+    }
+
+    /// A shortcut that fails keeps its entry unchanged, the others are still applied
+    fn apply_batch(
+        &mut self,
+        backends: &[&dyn ShortcutBackend],
+        indexes: Vec<usize>,
+        undo: bool,
+    ) -> Result<Vec<String>> {
+        let changed = indexes
+            .into_iter()
+            .filter_map(|index| {
+                self.apply(backends, index, undo)
+                    .inspect_err(|e| {
+                        error!(
+                            "{}:\n{}\n{e}",
+                            t!("ERROR_UNDO_REDO"),
+                            self.entries[index].link_path
+                        )
+                    })
+                    .ok()
+            })
+            .collect();
+        Ok(changed)
+    }
+}
+
+/// Icon changes recorded under one batch id - 同一批次的修改
+///
+/// The changes are merged into `journal.json` and `original_icons.json` once, when the batch
+/// is finished or dropped.
+pub struct Batch<'a> {
+    /// Folder of `journal.json` and `original_icons.json`
+    dir: PathBuf,
+    entries: Vec<JournalEntry>,
+    /// Loaded on first use and kept up to date with the batch's changes
+    original_icons: Option<OriginalIcons>,
+    /// Captured (`Some`) and restored (`None`) original icons, in order
+    original_icon_changes: Vec<(PathBuf, Option<IconLocation>)>,
+    backends: &'a [&'a dyn ShortcutBackend],
+}
+
+impl<'a> Batch<'a> {
+    pub fn new(dir: PathBuf, backends: &'a [&'a dyn ShortcutBackend]) -> Self {
+        Batch {
+            dir,
+            entries: Vec::new(),
+            original_icons: None,
+            original_icon_changes: Vec::new(),
+            backends,
+        }
+    }
+
+    /// Backend of the batch that writes the shortcut
+    pub fn backend(&self, link_path: &Path) -> Result<&'a dyn ShortcutBackend> {
+        backend_in(self.backends, link_path)
+    }
+
+    fn original_icons(&mut self) -> Result<&mut OriginalIcons> {
+        if self.original_icons.is_none() {
+            let original_icons = OriginalIcons::open(&self.dir.join(ORIGINAL_ICONS_FILE))?;
+            self.original_icons = Some(original_icons);
+        }
+        Ok(self.original_icons.get_or_insert_default())
+    }
+
+    /// Write the icon location and record the change, the first change captures the original icon
+    pub fn set_icon(&mut self, link_path: &Path, icon_path: &str, icon_index: i32) -> Result<()> {
+        let backend = self.backend(link_path)?;
+        let old = IconLocation::of(backend, link_path)?;
+        backend.set_icon(link_path, icon_path, icon_index)?;
+        let new = IconLocation::of(backend, link_path)?;
+
+        if backend.uses_original_icons() && old != new {
+            self.original_icons()?.capture(link_path, old.clone());
+            self.original_icon_changes
+                .push((link_path.to_path_buf(), Some(old.clone())));
+        }
+        self.record(link_path, old, new);
+        Ok(())
+    }
+
+    /// Put back the captured icon location and record the change
+    pub fn restore_icon(&mut self, link_prop: &LinkProp) -> Result<()> {
+        let link_path = Path::new(&link_prop.path);
+        let backend = self.backend(link_path)?;
+        let old = IconLocation::of(backend, link_path)?;
+
+        if backend.uses_original_icons() {
+            let original = self.original_icons()?.get(link_path).cloned();
+            backend.restore_icon(link_prop, original.as_ref())?;
+            self.original_icons()?.remove(link_path);
+            self.original_icon_changes
+                .push((link_path.to_path_buf(), None));
+        } else {
+            backend.restore_icon(link_prop, None)?;
+        }
+
+        let new = IconLocation::of(backend, link_path)?;
+        self.record(link_path, old, new);
+        Ok(())
+    }
+
+    fn record(&mut self, link_path: &Path, old: IconLocation, new: IconLocation) {
+        if old == new {
+            return;
+        }
+        self.entries.push(JournalEntry {
+            link_path: link_path.to_string_lossy().into_owned(),
+            old,
+            new,
+            timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            // 保存时分配
+            batch_id: 0,
+            undone: false,
+        });
+    }
+
+    /// Save the batch, reports the errors that dropping it can only log
+    pub fn finish(mut self) -> Result<()> {
+        self.save()
+    }
+
+    /// Merge the changes into the files as they are now, other processes may have saved since
+    fn save(&mut self) -> Result<()> {
+        let original_icon_changes = std::mem::take(&mut self.original_icon_changes);
+        if !original_icon_changes.is_empty() {
+            OriginalIcons::update(&self.dir.join(ORIGINAL_ICONS_FILE), |original_icons| {
+                for (link_path, change) in original_icon_changes {
+                    match change {
+                        Some(location) => original_icons.capture(&link_path, location),
+                        None => original_icons.remove(&link_path),
+                    }
+                }
+            })?;
+        }
+
+        let entries = std::mem::take(&mut self.entries);
+        if !entries.is_empty() {
+            Journal::update(&self.dir.join(JOURNAL_FILE), |journal| {
+                let batch_id = journal.next_batch_id;
+                journal.next_batch_id += 1;
+                for entry in entries {
+                    journal.record(JournalEntry { batch_id, ..entry });
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            error!("Failed to save the journal: {e}");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JournalScope {
+    /// The selected shortcut
+    Shortcut,
+    /// The latest batch, e.g. one run of "Change all"
+    Batch,
+}
+
+/// Undo icon changes and re-read the shortcuts, returns the number of changed shortcuts
+pub fn undo_icon_change(link_list: Signal<LinkList>, scope: JournalScope) -> Result<usize> {
+    step_icon_change(link_list, scope, true)
+}
+
+/// Redo icon changes and re-read the shortcuts, returns the number of changed shortcuts
+pub fn redo_icon_change(link_list: Signal<LinkList>, scope: JournalScope) -> Result<usize> {
+    step_icon_change(link_list, scope, false)
+}
+
+fn step_icon_change(link_list: Signal<LinkList>, scope: JournalScope, undo: bool) -> Result<usize> {
+    let backends = backends();
+    let changed = Journal::update(&Journal::path()?, |journal| {
+        Ok(match scope {
+            JournalScope::Shortcut => {
+                let link_path = {
+                    let link_list_read = link_list.read();
+                    let index = link_list_read.state.select.with_context(
+                        || "LinkList's State prompt does not have a selection icon",
+                    )?;
+                    link_list_read.items[index].path.clone()
+                };
+                match undo {
+                    true => journal.undo_shortcut(backends, &link_path)?,
+                    false => journal.redo_shortcut(backends, &link_path)?,
+                }
+                .into_iter()
+                .collect::<Vec<_>>()
+            }
+            JournalScope::Batch => match undo {
+                true => journal.undo_batch(backends)?,
+                false => journal.redo_batch(backends)?,
+            },
+        })
+    })?;
+
+    refresh_items(backends, link_list, &changed);
+    Ok(changed.len())
+}
+
+/// Re-read the changed shortcuts that are in the list
+pub fn refresh_items(
+    backends: &[&dyn ShortcutBackend],
+    mut link_list: Signal<LinkList>,
+    link_paths: &[String],
+) {
+    for link_path in link_paths {
+        let Some(index) = link_list
+            .read()
+            .items
+            .iter()
+            .position(|p| &p.path == link_path)
+        else {
+            continue;
+        };
+        let path = Path::new(link_path);
+        match backend_in(backends, path).and_then(|backend| backend.read(path)) {
+            Ok(link_prop) => link_list.write().items[index] = link_prop,
+            Err(e) => error!("Failed to get info:\n{link_path}\n{e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::backend::memory::MemoryBackend;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("linkecho-journal-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn icon(backend: &MemoryBackend, path: &str) -> Option<(String, i32)> {
+        backend.icon_location(Path::new(path)).unwrap()
+    }
+
+    fn memory_backend() -> MemoryBackend {
+        let backend = MemoryBackend::default();
+        backend.add(r"C:\Links\Chrome.lnk", None);
+        backend.add(r"C:\Links\Steam.lnk", Some((r"C:\Steam\steam.exe", 0)));
+        backend
+    }
+
+    #[test]
+    fn batch_is_undone_and_redone_together() {
+        let backend = memory_backend();
+        let backends: [&dyn ShortcutBackend; 1] = [&backend];
+        let dir = temp_dir("batch");
+        let path = dir.join(JOURNAL_FILE);
+
+        let mut batch = Batch::new(dir, &backends);
+        batch
+            .set_icon(Path::new(r"C:\Links\Chrome.lnk"), r"C:\Icons\chrome.ico", 0)
+            .unwrap();
+        batch
+            .set_icon(Path::new(r"C:\Links\Steam.lnk"), r"C:\Icons\steam.ico", 0)
+            .unwrap();
+        // 结束前不写入
+        assert!(!path.exists());
+        batch.finish().unwrap();
+        assert_eq!(Journal::open(&path).unwrap().entries.len(), 2);
+
+        let changed = Journal::update(&path, |journal| journal.undo_batch(&backends)).unwrap();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(icon(&backend, r"C:\Links\Chrome.lnk"), None);
+        assert_eq!(
+            icon(&backend, r"C:\Links\Steam.lnk"),
+            Some((r"C:\Steam\steam.exe".to_owned(), 0))
+        );
+
+        Journal::update(&path, |journal| journal.redo_batch(&backends)).unwrap();
+        assert_eq!(
+            icon(&backend, r"C:\Links\Chrome.lnk"),
+            Some((r"C:\Icons\chrome.ico".to_owned(), 0))
+        );
+
+        let journal = Journal::open(&path).unwrap();
+        assert!(journal.entries.iter().all(|e| !e.undone && e.batch_id == 0));
+        assert_eq!(journal.next_batch_id, 1);
+    }
+
+    #[test]
+    fn later_batch_is_undone_first() {
+        let backend = memory_backend();
+        let backends: [&dyn ShortcutBackend; 1] = [&backend];
+        let link_path = Path::new(r"C:\Links\Chrome.lnk");
+        let dir = temp_dir("order");
+
+        let mut batch = Batch::new(dir.clone(), &backends);
+        batch.set_icon(link_path, r"C:\Icons\a.ico", 0).unwrap();
+        batch.finish().unwrap();
+        let mut batch = Batch::new(dir.clone(), &backends);
+        batch.set_icon(link_path, r"C:\Icons\b.ico", 1).unwrap();
+        drop(batch);
+
+        let mut journal = Journal::open(&dir.join(JOURNAL_FILE)).unwrap();
+        journal.undo_batch(&backends).unwrap();
+        assert_eq!(
+            icon(&backend, r"C:\Links\Chrome.lnk"),
+            Some((r"C:\Icons\a.ico".to_owned(), 0))
+        );
+        journal
+            .undo_shortcut(&backends, r"C:\Links\Chrome.lnk")
+            .unwrap();
+        assert_eq!(icon(&backend, r"C:\Links\Chrome.lnk"), None);
+        assert_eq!(journal.undo_batch(&backends).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn overlapping_batches_keep_each_others_entries() {
+        let backend = memory_backend();
+        let backends: [&dyn ShortcutBackend; 1] = [&backend];
+        let dir = temp_dir("overlap");
+
+        // 两个批次同时进行，后结束的不会覆盖先结束的
+        let mut first = Batch::new(dir.clone(), &backends);
+        let mut second = Batch::new(dir.clone(), &backends);
+        first
+            .set_icon(Path::new(r"C:\Links\Chrome.lnk"), r"C:\Icons\chrome.ico", 0)
+            .unwrap();
+        second
+            .set_icon(Path::new(r"C:\Links\Steam.lnk"), r"C:\Icons\steam.ico", 0)
+            .unwrap();
+        first.finish().unwrap();
+        second.finish().unwrap();
+
+        let journal = Journal::open(&dir.join(JOURNAL_FILE)).unwrap();
+        let batch_ids = journal
+            .entries
+            .iter()
+            .map(|e| e.batch_id)
+            .collect::<Vec<_>>();
+        assert_eq!(batch_ids, [0, 1]);
+        assert_eq!(journal.next_batch_id, 2);
+
+        let original_icons = OriginalIcons::open(&dir.join(ORIGINAL_ICONS_FILE)).unwrap();
+        assert_eq!(
+            original_icons.get(Path::new(r"C:\Links\Chrome.lnk")),
+            Some(&IconLocation::default())
+        );
+        assert_eq!(
+            original_icons.get(Path::new(r"C:\Links\Steam.lnk")),
+            Some(&IconLocation {
+                path: r"C:\Steam\steam.exe".to_owned(),
+                index: 0
+            })
+        );
+    }
+
+    #[test]
+    fn failed_and_unchanged_shortcuts_are_not_recorded() {
+        let backend = memory_backend();
+        let backends: [&dyn ShortcutBackend; 1] = [&backend];
+        let dir = temp_dir("skip");
+
+        let mut batch = Batch::new(dir.clone(), &backends);
+        assert!(
+            batch
+                .set_icon(Path::new(r"C:\Links\Missing.lnk"), r"C:\Icons\a.ico", 0)
+                .is_err()
+        );
+        assert!(
+            batch
+                .set_icon(Path::new(r"C:\Links\Site.url"), r"C:\Icons\a.ico", 0)
+                .is_err()
+        );
+        batch
+            .set_icon(Path::new(r"C:\Links\Steam.lnk"), r"C:\Steam\steam.exe", 0)
+            .unwrap();
+        batch.finish().unwrap();
+        assert!(!dir.join(JOURNAL_FILE).exists());
+        assert!(!dir.join(ORIGINAL_ICONS_FILE).exists());
+    }
+
+    #[test]
+    fn restore_is_recorded() {
+        let backend = memory_backend();
+        let backends: [&dyn ShortcutBackend; 1] = [&backend];
+        let link_path = Path::new(r"C:\Links\Steam.lnk");
+        let dir = temp_dir("restore");
+
+        let mut batch = Batch::new(dir.clone(), &backends);
+        batch.set_icon(link_path, r"C:\Icons\a.ico", 0).unwrap();
+        batch.finish().unwrap();
+
+        let mut batch = Batch::new(dir.clone(), &backends);
+        batch.set_icon(link_path, r"C:\Icons\b.ico", 0).unwrap();
+        let link_prop = backend.read(link_path).unwrap();
+        batch.restore_icon(&link_prop).unwrap();
+        assert_eq!(
+            icon(&backend, r"C:\Links\Steam.lnk"),
+            Some((r"C:\Steam\steam.exe".to_owned(), 0))
+        );
+        batch.finish().unwrap();
+
+        let original_icons = OriginalIcons::open(&dir.join(ORIGINAL_ICONS_FILE)).unwrap();
+        assert_eq!(original_icons.get(link_path), None);
+
+        let mut journal = Journal::open(&dir.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(journal.entries.len(), 3);
+        journal.undo_batch(&backends).unwrap();
+        assert_eq!(
+            icon(&backend, r"C:\Links\Steam.lnk"),
+            Some((r"C:\Icons\a.ico".to_owned(), 0))
+        );
+    }
+}
@@ -1,6 +1,12 @@
-use super::info::{ManageLinkProp, SystemLinkDirs};
+use super::{
+    backend::{ShortcutBackend, backends, collect},
+    desktop_entry::application_dirs,
+    edit::ShowCommand,
+    info::SystemLinkDirs,
+};
 use crate::t;
 
+use anyhow::Result;
 use log::*;
 use std::path::PathBuf;
 
@@ -9,9 +15,11 @@ pub struct ListState {
     pub select: Option<usize>,
 }
 
+#[derive(Clone)]
 pub enum ShortcutSource {
     Desktop,
     StartMenu,
+    Applications,
     Other(PathBuf),
 }
 
@@ -20,11 +28,22 @@ impl ShortcutSource {
         match self {
             ShortcutSource::Desktop => t!("DESKTOP").into_owned(),
             ShortcutSource::StartMenu => t!("START_MENU").into_owned(),
+            ShortcutSource::Applications => t!("APPLICATIONS").into_owned(),
             ShortcutSource::Other(path) => path
                 .file_name()
                 .map_or("None".to_owned(), |n| n.to_string_lossy().into_owned()),
         }
     }
+
+    /// Folders scanned for this source
+    pub fn dirs(&self) -> Result<Vec<PathBuf>> {
+        match self {
+            ShortcutSource::Desktop => SystemLinkDirs::Desktop.get_path(),
+            ShortcutSource::StartMenu => SystemLinkDirs::StartMenu.get_path(),
+            ShortcutSource::Applications => Ok(application_dirs()),
+            ShortcutSource::Other(path) => Ok(vec![path.clone()]),
+        }
+    }
 }
 
 pub struct LinkList {
@@ -40,48 +59,38 @@ impl Default for LinkList {
 }
 
 impl LinkList {
-    pub fn desktop() -> Self {
-        let desktop_path = SystemLinkDirs::Desktop
-            .get_path()
-            .inspect_err(|e| error!("Failed to get Desktops path: {e}"))
-            .expect("Failed to get Desktops path");
-        let link_vec = ManageLinkProp::collect(&desktop_path)
-            .inspect_err(|e| error!("Failed to get properties of Desktop shortcuts: {e}"))
-            .expect("Failed to get properties of Desktop shortcuts");
+    /// Load the source's shortcuts through every registered backend
+    pub fn load(source: ShortcutSource) -> Self {
+        LinkList::load_with(backends(), source)
+    }
+
+    pub fn load_with(backends: &[&dyn ShortcutBackend], source: ShortcutSource) -> Self {
+        let dirs = source
+            .dirs()
+            .inspect_err(|e| error!("Failed to get {} path: {e}", source.name()))
+            .expect("Failed to get shortcuts path");
 
         Self {
-            items: link_vec,
+            items: collect(backends, &dirs),
             state: ListState::default(),
-            source: ShortcutSource::Desktop,
+            source,
         }
     }
 
+    pub fn desktop() -> Self {
+        LinkList::load(ShortcutSource::Desktop)
+    }
+
     pub fn start_menu() -> Self {
-        let start_path = SystemLinkDirs::StartMenu
-            .get_path()
-            .inspect_err(|e| error!("Failed to get Start Menu path: {e}"))
-            .expect("Failed to get Start Menu path");
-        let link_vec = ManageLinkProp::collect(&start_path)
-            .inspect_err(|e| error!("Failed to get properties of Start Menu shortcuts: {e}"))
-            .expect("Failed to get properties of Start Menu shortcuts");
+        LinkList::load(ShortcutSource::StartMenu)
+    }
 
-        Self {
-            items: link_vec,
-            state: ListState::default(),
-            source: ShortcutSource::StartMenu,
-        }
+    pub fn applications() -> Self {
+        LinkList::load(ShortcutSource::Applications)
     }
 
     pub fn other(path: PathBuf) -> Self {
-        let link_vec = ManageLinkProp::collect(&[path.clone()])
-            .inspect_err(|e| error!("Failed to get properties of desktop shortcuts: {e}"))
-            .expect("Failed to get properties of desktop shortcuts");
-
-        Self {
-            items: link_vec,
-            state: ListState::default(),
-            source: ShortcutSource::Other(path),
-        }
+        LinkList::load(ShortcutSource::Other(path))
     }
 }
 
@@ -98,13 +107,19 @@ pub struct LinkProp {
     pub path: String,
     pub status: Status,
     pub target_ext: String,
+    /// Working directory of the target, expanded or the target's folder
     pub target_dir: String,
+    /// Working directory as written in the shortcut, empty if none
+    pub working_dir: String,
     pub target_path: String,
     pub icon_base64: String,
     pub target_icon_base64: String,
     pub icon_path: String,
     pub icon_index: String,
     pub arguments: String,
+    pub description: String,
+    pub hotkey: String,
+    pub show_command: ShowCommand,
     pub file_size: String,
     pub created_at: String,
     pub updated_at: String,
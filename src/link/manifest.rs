@@ -0,0 +1,266 @@
+use super::normalize::normalize;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use log::*;
+use serde::Deserialize;
+
+const MANIFEST_JSON: &str = "pack.json";
+const MANIFEST_TOML: &str = "pack.toml";
+
+/// Optional `pack.json` or `pack.toml` at the root of an icon pack - 图标包清单
+///
+/// ```toml
+/// [icons.chrome]
+/// file = "browsers/chrome.ico"
+/// aliases = ["Google Chrome", "谷歌浏览器"]
+/// targets = ["chrome.exe"]
+/// keywords = ["browser"]
+/// variants = { dark = "browsers/chrome-dark.ico", mono = "mono/chrome.svg" }
+/// sizes = { 16 = "browsers/chrome-16.png", 32 = "browsers/chrome-32.png" }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PackManifest {
+    /// Icons by id, the id is also a name the icon matches
+    pub icons: HashMap<String, ManifestIcon>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ManifestIcon {
+    /// Relative to the pack root, defaults to the icon file named like the id
+    pub file: Option<PathBuf>,
+    /// Other names of the app, e.g. localized ones
+    pub aliases: Vec<String>,
+    /// Target file names, with or without extension
+    pub targets: Vec<String>,
+    /// Looser words, they only suggest alternatives
+    pub keywords: Vec<String>,
+    /// Named variants of the icon, e.g. "light", "dark", "mono"
+    pub variants: HashMap<String, PathBuf>,
+    /// Hand-made images of single frame sizes, used instead of resampling the default icon
+    pub sizes: HashMap<String, PathBuf>,
+}
+
+impl ManifestIcon {
+    /// Normalized names the icon matches exactly or fuzzily
+    pub fn names(&self, id: &str) -> Vec<String> {
+        let targets = self.targets.iter().map(|target| {
+            Path::new(target)
+                .file_stem()
+                .map_or(target.clone(), |stem| stem.to_string_lossy().into_owned())
+        });
+
+        std::iter::once(id.to_owned())
+            .chain(self.aliases.iter().cloned())
+            .chain(targets)
+            .map(|name| normalize(&name))
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    /// Files of `sizes` by frame size, a key that isn't a number is skipped
+    pub fn size_sources(&self, pack: &Path) -> BTreeMap<u32, PathBuf> {
+        self.sizes
+            .iter()
+            .filter_map(|(size, file)| match size.parse::<u32>() {
+                Ok(size) => Some((size, pack.join(file))),
+                Err(_) => {
+                    warn!("Invalid icon size '{size}' in the manifest of {pack:?}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl PackManifest {
+    /// `pack.json` is preferred when both exist
+    pub fn load(pack: &Path) -> Result<Option<Self>> {
+        let json_path = pack.join(MANIFEST_JSON);
+        if json_path.is_file() {
+            let data = std::fs::read(&json_path)?;
+            return serde_json::from_slice(&data)
+                .map(Some)
+                .with_context(|| format!("Failed to parse {json_path:?}"));
+        }
+
+        let toml_path = pack.join(MANIFEST_TOML);
+        if toml_path.is_file() {
+            let data = std::fs::read_to_string(&toml_path)?;
+            return toml::from_str(&data)
+                .map(Some)
+                .with_context(|| format!("Failed to parse {toml_path:?}"));
+        }
+
+        Ok(None)
+    }
+
+    /// Variant names offered by the pack
+    pub fn variants(&self) -> BTreeSet<String> {
+        self.icons
+            .values()
+            .flat_map(|icon| icon.variants.keys().cloned())
+            .collect()
+    }
+
+    /// Files of every variant, they are not icons of their own
+    pub fn variant_files(&self, pack: &Path) -> Vec<PathBuf> {
+        self.icons
+            .values()
+            .flat_map(|icon| icon.variants.values().map(|file| pack.join(file)))
+            .collect()
+    }
+
+    /// Files of every per-size image, they are frames of another icon
+    pub fn size_source_files(&self, pack: &Path) -> Vec<PathBuf> {
+        self.icons
+            .values()
+            .flat_map(|icon| icon.sizes.values().map(|file| pack.join(file)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::{packs::IconPacks, plan::load_icon_map};
+
+    const PACK_JSON: &str = r#"{
+        "icons": {
+            "chrome": {
+                "file": "browsers/chrome.ico",
+                "aliases": ["Google Chrome", "谷歌浏览器"],
+                "targets": ["chrome.exe"],
+                "keywords": ["browser"],
+                "variants": { "dark": "browsers/chrome-dark.ico" }
+            }
+        }
+    }"#;
+
+    const PACK_TOML: &str = r#"
+        [icons.code]
+        aliases = ["Visual Studio Code"]
+        targets = ["Code.exe"]
+        variants = { dark = "code-dark.ico", mono = "mono/code.ico" }
+        sizes = { 16 = "code-16.png", small = "code-s.png" }
+    "#;
+
+    /// A pack folder with the manifest files and empty icons
+    fn pack(name: &str, manifests: &[(&str, &str)], icons: &[&str]) -> PathBuf {
+        let pack = std::env::temp_dir().join(format!("linkecho-manifest-{name}"));
+        let _ = std::fs::remove_dir_all(&pack);
+        for icon in icons {
+            let path = pack.join(icon);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        std::fs::create_dir_all(&pack).unwrap();
+        for (file, content) in manifests {
+            std::fs::write(pack.join(file), content).unwrap();
+        }
+        pack
+    }
+
+    #[test]
+    fn json_aliases_and_targets_are_names() {
+        let pack = pack("json", &[(MANIFEST_JSON, PACK_JSON)], &[]);
+        let manifest = PackManifest::load(&pack).unwrap().unwrap();
+
+        let chrome = &manifest.icons["chrome"];
+        assert_eq!(
+            chrome.file.as_deref(),
+            Some(Path::new("browsers/chrome.ico"))
+        );
+        assert_eq!(
+            chrome.names("chrome"),
+            ["chrome", "google chrome", "谷歌浏览器", "chrome"]
+        );
+        assert_eq!(manifest.variants(), BTreeSet::from(["dark".to_owned()]));
+        assert_eq!(
+            manifest.variant_files(&pack),
+            [pack.join("browsers/chrome-dark.ico")]
+        );
+    }
+
+    #[test]
+    fn toml_variants_and_sizes_are_read() {
+        let pack = pack("toml", &[(MANIFEST_TOML, PACK_TOML)], &[]);
+        let manifest = PackManifest::load(&pack).unwrap().unwrap();
+
+        let code = &manifest.icons["code"];
+        assert_eq!(code.file, None);
+        assert_eq!(code.names("code"), ["code", "visual studio code", "code"]);
+        assert_eq!(
+            manifest.variants(),
+            BTreeSet::from(["dark".to_owned(), "mono".to_owned()])
+        );
+        let mut variant_files = manifest.variant_files(&pack);
+        variant_files.sort();
+        assert_eq!(
+            variant_files,
+            [pack.join("code-dark.ico"), pack.join("mono/code.ico")]
+        );
+        // 非数字的尺寸被跳过
+        assert_eq!(
+            code.size_sources(&pack),
+            BTreeMap::from([(16, pack.join("code-16.png"))])
+        );
+    }
+
+    #[test]
+    fn json_is_preferred_and_missing_manifest_is_none() {
+        let both = pack(
+            "both",
+            &[(MANIFEST_JSON, PACK_JSON), (MANIFEST_TOML, PACK_TOML)],
+            &[],
+        );
+        let manifest = PackManifest::load(&both).unwrap().unwrap();
+        assert!(manifest.icons.contains_key("chrome"));
+        assert!(!manifest.icons.contains_key("code"));
+
+        assert!(
+            PackManifest::load(&pack("none", &[], &[]))
+                .unwrap()
+                .is_none()
+        );
+        assert!(PackManifest::load(&pack("invalid", &[(MANIFEST_TOML, "[icons")], &[])).is_err());
+    }
+
+    #[test]
+    fn chosen_variant_replaces_the_default_icon() {
+        let pack = pack(
+            "icon_map",
+            &[(MANIFEST_JSON, PACK_JSON)],
+            &["browsers/chrome.ico", "browsers/chrome-dark.ico"],
+        );
+        let icon_map = |variant: Option<&str>| {
+            let packs = IconPacks {
+                dirs: vec![pack.clone()],
+                variant: variant.map(str::to_owned),
+                ..IconPacks::default()
+            };
+            load_icon_map(&pack, &packs).unwrap()
+        };
+
+        let default = icon_map(None);
+        let chrome = pack.join("browsers/chrome.ico");
+        for name in ["chrome", "google chrome", "谷歌浏览器"] {
+            assert_eq!(default.names.get(name), Some(&chrome), "{name}");
+        }
+        assert_eq!(default.keywords.get("browser"), Some(&chrome));
+        // 变体文件不单独参与匹配
+        let dark_file = pack.join("browsers/chrome-dark.ico");
+        assert!(!default.names.values().any(|file| *file == dark_file));
+
+        let dark = icon_map(Some("dark"));
+        assert_eq!(dark.names.get("google chrome"), Some(&dark_file));
+        // 没有该变体的图标使用默认文件
+        assert_eq!(icon_map(Some("mono")).names.get("chrome"), Some(&chrome));
+    }
+}
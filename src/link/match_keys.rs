@@ -0,0 +1,164 @@
+use super::{
+    list::LinkProp,
+    matcher::MatchKey,
+    normalize::{normalize, to_pinyin},
+};
+
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{Result, anyhow};
+use editpe::Image;
+use log::*;
+
+/// Targets whose version resource is read
+const VERSION_INFO_EXT: [&str; 2] = ["exe", "dll"];
+/// Interpreters, launchers and proxies: their name and version info belong to another program than the shortcut - 启动器
+const LAUNCHER_TARGETS: [&str; 26] = [
+    "cmd",
+    "powershell",
+    "pwsh",
+    "wscript",
+    "cscript",
+    "mshta",
+    "rundll32",
+    "msiexec",
+    "explorer",
+    "conhost",
+    "wsl",
+    "bash",
+    "sh",
+    "env",
+    "java",
+    "javaw",
+    "python",
+    "pythonw",
+    "node",
+    "retroarch",
+    "steam",
+    "update",
+    "chrome_proxy",
+    "msedge_proxy",
+    "flatpak",
+    "gtk-launch",
+];
+/// Browsers start installed web apps with this argument
+const WEB_APP_ARG: &str = "--app-id=";
+
+/// Texts a shortcut can be matched by, the shortcut name first - 匹配依据
+///
+/// The target's file stem and version strings stay the same when the shortcut name
+/// is localized or branded, e.g. "网易云音乐" -> "cloudmusic". With `pinyin` a Chinese
+/// name is also tried transliterated, e.g. "微信" -> "weixin".
+/// Targets that launch another program, see `is_launcher`, only give the name keys.
+pub fn match_keys(link_prop: &LinkProp, pinyin: bool) -> Vec<(MatchKey, String)> {
+    let mut keys = vec![(MatchKey::Name, link_prop.name.clone())];
+    if pinyin {
+        keys.extend(to_pinyin(&link_prop.name).map(|text| (MatchKey::Pinyin, text)));
+    }
+
+    let target_path = Path::new(&link_prop.target_path);
+    if target_path.is_file() && !is_launcher(target_path, &link_prop.arguments) {
+        if let Some(stem) = target_path.file_stem().and_then(OsStr::to_str) {
+            keys.push((MatchKey::TargetName, stem.to_owned()));
+        }
+
+        let is_pe = target_path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|ext| VERSION_INFO_EXT.contains(&ext.to_lowercase().as_str()));
+        if is_pe {
+            match version_strings(target_path) {
+                Ok((product_name, original_filename)) => {
+                    keys.extend(product_name.map(|n| (MatchKey::ProductName, n)));
+                    keys.extend(original_filename.map(|n| (MatchKey::OriginalFilename, n)));
+                }
+                Err(e) => debug!("No version info: {target_path:?}\n{e}"),
+            }
+        }
+    }
+
+    // 同名的依据只保留第一个
+    let mut unique: Vec<(MatchKey, String)> = Vec::new();
+    for (key, text) in keys {
+        let text = normalize(&text);
+        if !text.is_empty() && unique.iter().all(|(_, t)| *t != text) {
+            unique.push((key, text));
+        }
+    }
+    unique
+}
+
+/// Interpreters, script hosts, game launchers and browsers that start a web app
+fn is_launcher(target_path: &Path, arguments: &str) -> bool {
+    let is_launcher_target = target_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .is_some_and(|stem| LAUNCHER_TARGETS.contains(&stem.to_lowercase().as_str()));
+    is_launcher_target || arguments.contains(WEB_APP_ARG)
+}
+
+/// `ProductName` and `OriginalFilename` (without extension) of a PE file's version resource
+fn version_strings(pe_path: &Path) -> Result<(Option<String>, Option<String>)> {
+    let image = Image::parse_file(pe_path).map_err(|e| anyhow!("Failed to parse: {e}"))?;
+    let Some(version_info) = image
+        .resource_directory()
+        .map(|resources| resources.get_version_info())
+        .transpose()
+        .map_err(|e| anyhow!("Invalid version info: {e}"))?
+        .flatten()
+    else {
+        return Ok((None, None));
+    };
+
+    // 任一语言的字符串表中的第一个值
+    let string_of = |name: &str| {
+        version_info
+            .strings
+            .iter()
+            .find_map(|table| table.strings.get(name))
+            .map(|value| value.trim_end_matches('\0').to_owned())
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    let product_name = string_of("ProductName");
+    let original_filename = string_of("OriginalFilename").map(|name| {
+        Path::new(&name)
+            .file_stem()
+            .map_or(name.clone(), |stem| stem.to_string_lossy().into_owned())
+    });
+
+    Ok((product_name, original_filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launchers_are_recognized() {
+        assert!(is_launcher(
+            Path::new("C:/Windows/System32/cmd.exe"),
+            "/c start.bat"
+        ));
+        assert!(is_launcher(
+            Path::new("C:/Java/bin/javaw.exe"),
+            "-jar app.jar"
+        ));
+        assert!(is_launcher(
+            Path::new("C:/Program Files/Google/Chrome/Application/chrome_proxy.exe"),
+            "--profile-directory=Default"
+        ));
+        assert!(is_launcher(
+            Path::new("C:/Program Files/Google/Chrome/Application/chrome.exe"),
+            "--profile-directory=Default --app-id=agimnkijcaahngcdmfeangaknmldooml"
+        ));
+        assert!(!is_launcher(
+            Path::new("C:/Program Files/Google/Chrome/Application/chrome.exe"),
+            ""
+        ));
+        assert!(!is_launcher(
+            Path::new("C:/Games/Far Cry 5/FarCry5.exe"),
+            ""
+        ));
+    }
+}
@@ -0,0 +1,396 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+
+/// Candidates scoring below this are not used - 匹配置信度阈值
+pub const MATCH_THRESHOLD: f64 = 0.9;
+/// Words that make a shortcut another program than the one it names, "Uninstall Chrome" is not "Chrome"
+const ACTION_WORDS: [&str; 14] = [
+    "uninstall",
+    "uninstaller",
+    "unins",
+    "update",
+    "updater",
+    "setup",
+    "install",
+    "installer",
+    "repair",
+    "readme",
+    "help",
+    "manual",
+    "license",
+    "website",
+];
+/// Token similarity from which two words count as the same
+const SAME_WORD: f64 = 0.9;
+
+/// What a shortcut is matched by - 匹配依据
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchKey {
+    /// Shortcut name, often localized or branded
+    #[default]
+    Name,
+    /// Pinyin of a Chinese shortcut name
+    Pinyin,
+    /// File stem of the target
+    TargetName,
+    /// `ProductName` of the target's version resource
+    ProductName,
+    /// `OriginalFilename` of the target's version resource, without extension
+    OriginalFilename,
+}
+
+impl MatchKey {
+    /// Keys taken from the shortcut name, the target keys are only tried when these give no candidate
+    pub fn is_name(self) -> bool {
+        matches!(self, MatchKey::Name | MatchKey::Pinyin)
+    }
+
+    /// Product names are long and generic, "Microsoft Windows Operating System" must not take "windows"
+    fn weight(self) -> f64 {
+        match self {
+            MatchKey::ProductName => 0.95,
+            _ => 1.0,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            MatchKey::Name => t!("MATCH_KEY_NAME").into_owned(),
+            MatchKey::Pinyin => t!("MATCH_KEY_PINYIN").into_owned(),
+            MatchKey::TargetName => t!("MATCH_KEY_TARGET_NAME").into_owned(),
+            MatchKey::ProductName => t!("MATCH_KEY_PRODUCT_NAME").into_owned(),
+            MatchKey::OriginalFilename => t!("MATCH_KEY_ORIGINAL_FILENAME").into_owned(),
+        }
+    }
+}
+
+/// Icon candidate with its similarity to the shortcut, from 0.0 to 1.0
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IconMatch {
+    pub name: String,
+    pub path: PathBuf,
+    pub score: f64,
+    /// The key that scored best and its lowercase text
+    #[serde(default)]
+    pub key: MatchKey,
+    #[serde(default)]
+    pub key_text: String,
+}
+
+/// Score every icon of `icon_map` against every key of the shortcut, best first
+///
+/// Each icon keeps its best scoring key, the earlier key on a tie.
+pub fn rank_icons(
+    icon_map: &HashMap<String, PathBuf>,
+    keys: &[(MatchKey, String)],
+) -> Vec<IconMatch> {
+    let mut candidates = icon_map
+        .iter()
+        .filter_map(|(icon_name, icon_path)| {
+            keys.iter()
+                .map(|(key, key_text)| IconMatch {
+                    name: icon_name.clone(),
+                    path: icon_path.clone(),
+                    score: similarity(key_text, icon_name) * key.weight(),
+                    key: *key,
+                    key_text: key_text.clone(),
+                })
+                .reduce(|best, m| if m.score > best.score { m } else { best })
+        })
+        .collect::<Vec<_>>();
+
+    // 分数相同时优先长度接近的名称
+    let length_diff = |m: &IconMatch| m.name.chars().count().abs_diff(m.key_text.chars().count());
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(length_diff(a).cmp(&length_diff(b)))
+            .then(a.name.cmp(&b.name))
+    });
+    candidates
+}
+
+/// Similarity of two names, tolerant of word order, extra words, versions and acronyms
+///
+/// "Google Chrome" ~ "chrome", "VS Code" ~ "Visual Studio Code", "Photoshop 2024" ~ "Adobe Photoshop".
+/// Every action word or number without a counterpart halves the score, "Far Cry 4" is not "Far Cry 5".
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a_tokens = tokenize(a);
+    let b_tokens = tokenize(b);
+    if a_tokens.is_empty() || b_tokens.is_empty() {
+        return token_similarity(&a.to_lowercase(), &b.to_lowercase());
+    }
+
+    let a_tokens = expand_acronyms(a_tokens, &b_tokens);
+    let b_tokens = expand_acronyms(b_tokens, &a_tokens);
+
+    let (short, long) = match a_tokens.len() <= b_tokens.len() {
+        true => (&a_tokens, &b_tokens),
+        false => (&b_tokens, &a_tokens),
+    };
+    let best = |token: &String, others: &[String]| {
+        others
+            .iter()
+            .map(|other| token_similarity(token, other))
+            .fold(0.0, f64::max)
+    };
+    let mean = |scores: Vec<f64>| scores.iter().sum::<f64>() / scores.len() as f64;
+
+    // 较短名称的每个词都应出现在较长名称中，较长名称多出的词只略微降低分数
+    let containment = mean(short.iter().map(|t| best(t, long)).collect());
+    let coverage = mean(long.iter().map(|t| best(t, short)).collect());
+    let token_score = containment * (0.9 + 0.1 * coverage);
+
+    let whole_score = token_similarity(&a_tokens.concat(), &b_tokens.concat());
+
+    let unmatched = [(&a_tokens, &b_tokens), (&b_tokens, &a_tokens)]
+        .into_iter()
+        .flat_map(|(tokens, others)| {
+            tokens.iter().filter(|t| match is_number(t) {
+                true => !others.contains(t),
+                false => ACTION_WORDS.contains(&t.as_str()) && best(t, others) < SAME_WORD,
+            })
+        })
+        .count();
+    token_score.max(whole_score) * 0.5_f64.powi(unmatched as i32)
+}
+
+/// Lowercase words, split on separators and camelCase, without version numbers
+///
+/// Dotted numbers stay one word, "64-bit" becomes "64bit".
+/// Keeps every word if all of them look like versions.
+pub fn tokenize(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut prev: Option<char> = None;
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        let in_number = c == '.'
+            && prev.is_some_and(|p| p.is_ascii_digit())
+            && chars.peek().is_some_and(char::is_ascii_digit);
+        if !c.is_alphanumeric() && !in_number {
+            words.push(std::mem::take(&mut current));
+            prev = None;
+            continue;
+        }
+        if prev.is_some_and(|p| p.is_lowercase() && c.is_uppercase()) {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+        prev = Some(c);
+    }
+    words.push(current);
+    words.retain(|w| !w.is_empty());
+
+    // "64-bit" 合并为一个词
+    let mut merged: Vec<String> = Vec::new();
+    for word in words {
+        match merged.last_mut() {
+            Some(last) if word == "bit" && is_number(last) => last.push_str("bit"),
+            _ => merged.push(word),
+        }
+    }
+    let words = merged;
+
+    let significant = words
+        .iter()
+        .filter(|w| !is_version(w))
+        .cloned()
+        .collect::<Vec<_>>();
+    match significant.is_empty() {
+        true => words,
+        false => significant,
+    }
+}
+
+fn is_number(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())
+}
+
+/// v2, v2.1, 3.12, x64, 64bit and release years like 2024, a bare number is part of the name
+fn is_version(word: &str) -> bool {
+    let is_dotted = |w: &str| w.contains('.') && w.split('.').all(is_number);
+    // 与 normalize 相同，1990 ~ 2039 年
+    let is_year = word.len() == 4
+        && is_number(word)
+        && (1990..=2039).contains(&word.parse::<u32>().unwrap_or(0));
+    match word.strip_prefix('v') {
+        Some(version) => is_number(version) || is_dotted(version),
+        None => {
+            matches!(word, "x64" | "x86")
+                || word.strip_suffix("bit").is_some_and(is_number)
+                || is_dotted(word)
+                || is_year
+        }
+    }
+}
+
+/// Replace a token that is the initials of consecutive `others` tokens by those tokens
+fn expand_acronyms(tokens: Vec<String>, others: &[String]) -> Vec<String> {
+    tokens
+        .into_iter()
+        .flat_map(|token| {
+            let len = token.chars().count();
+            let expanded = (len >= 2 && !others.contains(&token))
+                .then(|| {
+                    others.windows(len).find(|run| {
+                        run.iter()
+                            .filter_map(|t| t.chars().next())
+                            .eq(token.chars())
+                    })
+                })
+                .flatten();
+            match expanded {
+                Some(run) => run.to_vec(),
+                None => vec![token],
+            }
+        })
+        .collect()
+}
+
+/// Mean of Jaro-Winkler and normalized edit distance, so "word" and "wordpad" stay apart
+fn token_similarity(a: &str, b: &str) -> f64 {
+    (jaro_winkler(a, b) + normalized_levenshtein(a, b)) / 2.0
+}
+
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0;
+    for (i, ac) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matched[j] && *ac == b[j] {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let b_in_order = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let transpositions = a
+        .iter()
+        .zip(&a_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c)
+        .zip(b_in_order)
+        .filter(|(x, y)| x != y)
+        .count();
+
+    let m = matches as f64;
+    let jaro =
+        (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64 / 2.0) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+pub fn normalized_levenshtein(a: &str, b: &str) -> f64 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ac) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, bc) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ac != bc);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    1.0 - row[b.len()] as f64 / max_len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_of_the_same_program_match() {
+        for (a, b) in [
+            ("Google Chrome", "chrome"),
+            ("VS Code", "Visual Studio Code"),
+            ("Photoshop 2024", "Adobe Photoshop"),
+            ("steam", "Steam"),
+            ("OBS Studio", "obs-studio"),
+            ("Far Cry 5", "far cry 5"),
+            ("Python 3.12 (64-bit)", "python"),
+            ("Notepad++ v8.6 x64", "notepad++"),
+        ] {
+            let score = similarity(a, b);
+            assert!(score >= MATCH_THRESHOLD, "{a} ~ {b}: {score}");
+        }
+    }
+
+    #[test]
+    fn names_of_other_programs_do_not_match() {
+        for (a, b) in [
+            ("Uninstall Chrome", "chrome"),
+            ("Firefox Update", "firefox"),
+            ("Steam Setup", "steam"),
+            ("word", "wordpad"),
+            ("Microsoft Edge", "Microsoft Word"),
+            ("Far Cry 4", "far cry 5"),
+            ("Windows 10", "windows 11"),
+            ("7-Zip", "zip"),
+            ("Battlefield 1942", "battlefield 2042"),
+            ("Cyberpunk 2077", "cyberpunk"),
+        ] {
+            let score = similarity(a, b);
+            assert!(score < MATCH_THRESHOLD, "{a} ~ {b}: {score}");
+        }
+    }
+
+    #[test]
+    fn only_versions_are_dropped() {
+        assert_eq!(tokenize("Far Cry 5"), ["far", "cry", "5"]);
+        assert_eq!(tokenize("7-Zip"), ["7", "zip"]);
+        assert_eq!(tokenize("Python 3.12 (64-bit)"), ["python"]);
+        assert_eq!(tokenize("App v2.1 x86"), ["app"]);
+        assert_eq!(tokenize("Photoshop 2024"), ["photoshop"]);
+        assert_eq!(tokenize("2048"), ["2048"]);
+    }
+
+    #[test]
+    fn matching_action_words_are_not_penalized() {
+        assert!(similarity("Uninstall Chrome", "chrome uninstall") >= MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn best_candidate_is_ranked_first() {
+        let icon_map = ["chrome", "chromium", "google earth"]
+            .into_iter()
+            .map(|name| (name.to_owned(), PathBuf::from(format!("{name}.ico"))))
+            .collect::<HashMap<_, _>>();
+        let keys = [(MatchKey::Name, "google chrome".to_owned())];
+        let ranked = rank_icons(&icon_map, &keys);
+        assert_eq!(ranked[0].name, "chrome");
+        assert_eq!(ranked.len(), 3);
+    }
+}
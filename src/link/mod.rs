@@ -1,4 +1,26 @@
+pub mod ansi;
+pub mod backend;
+pub mod conversion_cache;
+pub mod create;
+pub mod desktop_entry;
+pub mod edit;
+pub mod file_lock;
+pub mod icon_gc;
 pub mod info;
+pub mod ini;
+pub mod internet_shortcut;
+pub mod journal;
 pub mod list;
+pub mod manifest;
+pub mod match_keys;
+pub mod matcher;
 pub mod modify;
+pub mod normalize;
+pub mod original_icon;
+pub mod packs;
+pub mod plan;
+pub mod rules;
+pub mod shell_link;
+pub mod size_sources;
+pub mod snapshot;
 pub mod utils;
@@ -0,0 +1,86 @@
+use std::sync::LazyLock;
+
+use pinyin::ToPinyin;
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+/// Trailing versions and architectures: " 2024", " v2.1", " 3.12", " (x64)", " [64-bit]"
+///
+/// A bare number is part of the name, e.g. "Far Cry 5" or "Cyberpunk 2077", only release
+/// years from 1990 to 2039 like "2024" are stripped.
+static VERSION_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
+    let version =
+        r"(?:v\d+(?:\.\d+)*|\d+(?:\.\d+)+|199\d|20[0-3]\d|x64|x86|x86_64|amd64|arm64|\d+-?bit)";
+    Regex::new(&format!(r"(?:\s*[(\[]\s*{version}\s*[)\]]|\s+{version})+$"))
+        .expect("Invalid version suffix pattern")
+});
+
+/// Form of a name that shortcuts and icons are compared in - 名称规范化
+///
+/// NFKC folding (which also turns full-width letters, digits and brackets into ASCII),
+/// lowercase, single spaces and no version suffix, e.g. "Ｐｙｔｈｏｎ 3.12 (64-bit)" -> "python".
+pub fn normalize(name: &str) -> String {
+    let folded = name
+        .nfkc()
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // 名称仅为版本号时保留原样
+    match VERSION_SUFFIX.replace(&folded, "").trim() {
+        "" => folded,
+        stripped => stripped.to_owned(),
+    }
+}
+
+/// Toneless pinyin of the Chinese characters, other characters are kept, e.g. "微信" -> "weixin"
+///
+/// `None` when the name has no Chinese characters.
+pub fn to_pinyin(name: &str) -> Option<String> {
+    let mut has_han = false;
+    let text = name
+        .chars()
+        .map(|c| match c.to_pinyin() {
+            Some(pinyin) => {
+                has_han = true;
+                pinyin.plain().to_owned()
+            }
+            None => c.to_string(),
+        })
+        .collect::<String>();
+    has_han.then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_stripped() {
+        assert_eq!(normalize("Ｐｙｔｈｏｎ 3.12 (64-bit)"), "python");
+        assert_eq!(normalize("Photoshop 2024"), "photoshop");
+        assert_eq!(normalize("Tool v2 [x64]"), "tool");
+    }
+
+    #[test]
+    fn small_numbers_are_kept() {
+        assert_eq!(normalize("Far Cry 5"), "far cry 5");
+        assert_eq!(normalize("Windows 10"), "windows 10");
+        assert_eq!(normalize("Half-Life  2"), "half-life 2");
+    }
+
+    #[test]
+    fn titles_ending_in_four_digits_are_kept() {
+        assert_eq!(normalize("Cyberpunk 2077"), "cyberpunk 2077");
+        assert_eq!(normalize("Anno 1800"), "anno 1800");
+        assert_eq!(normalize("Battlefield 1942"), "battlefield 1942");
+        assert_eq!(normalize("Battlefield 2042"), "battlefield 2042");
+    }
+
+    #[test]
+    fn name_of_only_a_version_is_kept() {
+        assert_eq!(normalize("2048"), "2048");
+    }
+}
@@ -0,0 +1,87 @@
+use super::{file_lock::FileLock, journal::IconLocation};
+use crate::utils::ensure_local_app_folder_exists;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const ORIGINAL_ICONS_FILE: &str = "original_icons.json";
+
+/// Icon locations of .lnk and .url shortcuts before LinkEcho first changed them - 快捷方式的原始图标位置
+///
+/// .desktop files keep their original icon in the file itself.
+/// The location is stored unexpanded, an empty path means the shortcut had no explicit icon.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OriginalIcons(HashMap<String, IconLocation>);
+
+impl OriginalIcons {
+    fn path() -> Result<PathBuf> {
+        Ok(ensure_local_app_folder_exists()?.join(ORIGINAL_ICONS_FILE))
+    }
+
+    /// 路径不区分大小写
+    fn key(link_path: &Path) -> String {
+        link_path.to_string_lossy().to_lowercase()
+    }
+
+    pub fn load() -> Result<Self> {
+        OriginalIcons::open(&OriginalIcons::path()?)
+    }
+
+    /// Original icons saved at `path`, empty if the file doesn't exist yet
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(OriginalIcons::default());
+        }
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Reload, change and save the file under its lock, so captures of other processes are kept
+    pub fn update(path: &Path, f: impl FnOnce(&mut OriginalIcons)) -> Result<()> {
+        let _lock = FileLock::acquire(path)?;
+        let mut original_icons = OriginalIcons::open(path)?;
+        f(&mut original_icons);
+        original_icons.save(path)
+    }
+
+    pub fn get(&self, link_path: &Path) -> Option<&IconLocation> {
+        self.0.get(&OriginalIcons::key(link_path))
+    }
+
+    /// Lowercase paths of the shortcuts with a captured icon
+    pub fn link_paths(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Captured icon paths, without the empty ones of shortcuts that had no icon
+    pub fn icon_paths(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .values()
+            .map(|location| location.path.as_str())
+            .filter(|path| !path.is_empty())
+    }
+
+    /// Keep `location` unless one was captured before
+    pub fn capture(&mut self, link_path: &Path, location: IconLocation) {
+        self.0
+            .entry(OriginalIcons::key(link_path))
+            .or_insert(location);
+    }
+
+    /// Forget the original icon once it is restored
+    pub fn remove(&mut self, link_path: &Path) {
+        self.0.remove(&OriginalIcons::key(link_path));
+    }
+}
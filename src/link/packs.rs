@@ -0,0 +1,256 @@
+use crate::{image::icongen::image_side, utils::ensure_local_app_folder_exists};
+
+use std::{
+    cmp::Ordering,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+const ICON_PACKS_FILE: &str = "icon_packs.json";
+
+/// Icon folders used by the batch change, highest priority first - 图标包
+///
+/// A shortcut takes its icon from the first pack that matches it, lower packs fill the gaps.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IconPacks {
+    pub dirs: Vec<PathBuf>,
+    /// Variant of `pack.json` to use, e.g. "dark", the default icons when `None`
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// Also match Chinese shortcut names by their pinyin, e.g. "微信" -> "weixin.ico"
+    #[serde(default)]
+    pub pinyin: bool,
+    /// Which file wins when several icons of a pack share a name
+    #[serde(default)]
+    pub duplicates: DuplicatePolicy,
+}
+
+/// One criterion of `DuplicatePolicy::prefer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preference {
+    /// Earlier extension of `DuplicatePolicy::formats`
+    Format,
+    /// Fewer folders below the pack root
+    Shallower,
+    Deeper,
+    /// Longer image side, SVG counts as the largest
+    Larger,
+    Smaller,
+}
+
+/// Tie-breaking between icon files that share a name - 同名图标的取舍
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicatePolicy {
+    /// Extensions, most preferred first, unlisted ones come last
+    pub formats: Vec<String>,
+    /// Criteria tried in order, the path decides when all of them are equal
+    pub prefer: Vec<Preference>,
+}
+
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        Self {
+            formats: ["ico", "svg", "png", "webp", "bmp", "tiff", "exe"]
+                .map(str::to_owned)
+                .to_vec(),
+            prefer: vec![
+                Preference::Format,
+                Preference::Shallower,
+                Preference::Larger,
+            ],
+        }
+    }
+}
+
+impl DuplicatePolicy {
+    /// `Less` when `a` is preferred over `b`
+    pub fn compare(&self, pack: &Path, a: &Path, b: &Path) -> Ordering {
+        let format = |path: &Path| {
+            let ext = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+            self.formats
+                .iter()
+                .position(|format| format.eq_ignore_ascii_case(ext))
+                .unwrap_or(self.formats.len())
+        };
+        let depth = |path: &Path| {
+            path.strip_prefix(pack)
+                .map_or(0, |relative| relative.components().count())
+        };
+
+        self.prefer
+            .iter()
+            .fold(Ordering::Equal, |ordering, preference| {
+                ordering.then_with(|| match preference {
+                    Preference::Format => format(a).cmp(&format(b)),
+                    Preference::Shallower => depth(a).cmp(&depth(b)),
+                    Preference::Deeper => depth(b).cmp(&depth(a)),
+                    Preference::Larger => image_side(b).cmp(&image_side(a)),
+                    Preference::Smaller => image_side(a).cmp(&image_side(b)),
+                })
+            })
+            .then_with(|| a.cmp(b))
+    }
+}
+
+impl IconPacks {
+    fn path() -> Result<PathBuf> {
+        Ok(ensure_local_app_folder_exists()?.join(ICON_PACKS_FILE))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = IconPacks::path()?;
+        if !path.is_file() {
+            return Ok(IconPacks::default());
+        }
+        let data = std::fs::read(&path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::write(IconPacks::path()?, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Add a pack with the lowest priority, a pack already in the list is kept where it is
+    pub fn add(&mut self, pack: PathBuf) {
+        if !self.dirs.contains(&pack) {
+            self.dirs.push(pack);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.dirs.len() {
+            self.dirs.remove(index);
+        }
+    }
+
+    /// Swap a pack with the one above it
+    pub fn raise(&mut self, index: usize) {
+        if index > 0 && index < self.dirs.len() {
+            self.dirs.swap(index - 1, index);
+        }
+    }
+
+    /// Pack that contains the icon
+    pub fn pack_of(&self, icon_path: &Path) -> Option<&PathBuf> {
+        self.dirs.iter().find(|pack| icon_path.starts_with(pack))
+    }
+
+    pub fn name(pack: &Path) -> String {
+        pack.file_name()
+            .map_or_else(|| pack.to_string_lossy(), |n| n.to_string_lossy())
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::plan::load_icon_map;
+
+    use image::RgbaImage;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("linkecho-packs-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A PNG of the given side, or an empty file for other extensions
+    fn icon(path: &Path, side: u32) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        match side {
+            0 => std::fs::write(path, b"").unwrap(),
+            side => RgbaImage::new(side, side).save(path).unwrap(),
+        }
+    }
+
+    /// Icon of `name` in the first pack that has one, as the batch change picks it
+    fn first_icon(packs: &IconPacks, name: &str) -> Option<PathBuf> {
+        packs.dirs.iter().find_map(|pack| {
+            let mut icon_map = load_icon_map(pack, packs).unwrap();
+            icon_map.names.remove(name)
+        })
+    }
+
+    #[test]
+    fn higher_pack_wins_for_the_same_name() {
+        let dir = temp_dir("priority");
+        let (high, low) = (dir.join("high"), dir.join("low"));
+        icon(&high.join("app.ico"), 0);
+        icon(&low.join("App.ico"), 0);
+        icon(&low.join("other.ico"), 0);
+
+        let mut packs = IconPacks::default();
+        packs.add(high.clone());
+        packs.add(low.clone());
+        packs.add(high.clone());
+        assert_eq!(packs.dirs, [high.clone(), low.clone()]);
+
+        assert_eq!(first_icon(&packs, "app"), Some(high.join("app.ico")));
+        // 高优先级的图标包没有时由低优先级的补充
+        assert_eq!(first_icon(&packs, "other"), Some(low.join("other.ico")));
+        assert_eq!(packs.pack_of(&low.join("App.ico")), Some(&low));
+        assert_eq!(IconPacks::name(&low), "low");
+
+        packs.raise(1);
+        assert_eq!(first_icon(&packs, "app"), Some(low.join("App.ico")));
+        packs.remove(0);
+        assert_eq!(first_icon(&packs, "app"), Some(high.join("app.ico")));
+        assert_eq!(packs.pack_of(&low.join("App.ico")), None);
+    }
+
+    #[test]
+    fn duplicate_names_follow_the_policy() {
+        let dir = temp_dir("duplicates");
+        let pack = dir.join("pack");
+        icon(&pack.join("app.png"), 16);
+        icon(&pack.join("large").join("app.png"), 64);
+        icon(&pack.join("nested").join("ico").join("app.ico"), 0);
+
+        let kept = |prefer: Vec<Preference>| {
+            let packs = IconPacks {
+                dirs: vec![pack.clone()],
+                duplicates: DuplicatePolicy {
+                    prefer,
+                    ..DuplicatePolicy::default()
+                },
+                ..IconPacks::default()
+            };
+            let icon_map = load_icon_map(&pack, &packs).unwrap();
+            assert_eq!(icon_map.duplicates.len(), 1);
+            let duplicate = &icon_map.duplicates[0];
+            assert_eq!(
+                (duplicate.name.as_str(), duplicate.shadowed.len()),
+                ("app", 2)
+            );
+            assert_eq!(icon_map.names["app"], duplicate.kept);
+            duplicate.kept.strip_prefix(&pack).unwrap().to_path_buf()
+        };
+
+        // 默认：.ico优先
+        assert_eq!(
+            kept(DuplicatePolicy::default().prefer),
+            Path::new("nested/ico/app.ico")
+        );
+        assert_eq!(kept(vec![Preference::Shallower]), Path::new("app.png"));
+        assert_eq!(
+            kept(vec![Preference::Deeper]),
+            Path::new("nested/ico/app.ico")
+        );
+        assert_eq!(kept(vec![Preference::Larger]), Path::new("large/app.png"));
+        // 无法读取的图标尺寸为0
+        assert_eq!(
+            kept(vec![Preference::Smaller]),
+            Path::new("nested/ico/app.ico")
+        );
+        // 条件均相同时按路径
+        assert_eq!(kept(Vec::new()), Path::new("app.png"));
+    }
+}
@@ -0,0 +1,849 @@
+use super::{
+    backend::ShortcutBackend,
+    conversion_cache::ICONS_DIR,
+    info::ManageLinkProp,
+    journal::{Batch, Journal},
+    list::{LinkProp, ShortcutSource},
+    manifest::PackManifest,
+    match_keys::match_keys,
+    matcher::{IconMatch, MATCH_THRESHOLD, MatchKey, rank_icons},
+    normalize::normalize,
+    packs::{DuplicatePolicy, IconPacks},
+    rules::{RuleAction, Rules},
+    size_sources::size_suffix,
+    utils::{converted_icon_path, process_icon},
+};
+use crate::{
+    image::customize::{IconStyle, render_styled_icon},
+    utils::ensure_local_app_folder_exists,
+};
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use glob::glob;
+use log::*;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+pub const MATCH_ICON_EXT: [&str; 7] = ["ico", "png", "svg", "bmp", "webp", "tiff", "exe"];
+/// Keywords only suggest alternatives, they never reach `MATCH_THRESHOLD`
+const KEYWORD_WEIGHT: f64 = 0.85;
+/// Other candidates worth showing next to the chosen icon
+const ALTERNATIVE_THRESHOLD: f64 = 0.6;
+const MAX_ALTERNATIVES: usize = 4;
+
+/// Icons of a pack by normalized name - 图标包中的图标
+#[derive(Debug, Default)]
+pub struct IconMap {
+    pub names: HashMap<String, PathBuf>,
+    /// Keywords of the pack manifest, weaker than names
+    pub keywords: HashMap<String, PathBuf>,
+    /// Files hidden by another file of the same name
+    pub duplicates: Vec<DuplicateIcons>,
+}
+
+/// File stems of the pack, then the names of its `pack.json`, which use the chosen variant
+pub fn load_icon_map(pack: &Path, packs: &IconPacks) -> Result<IconMap> {
+    let (mut names, mut duplicates) = scan_icons(pack, &packs.duplicates)?;
+    for duplicate in &duplicates {
+        info!(
+            "{}: {}\n{:?}\n{:?}",
+            t!("DUPLICATE_ICONS"),
+            duplicate.name,
+            duplicate.kept,
+            duplicate.shadowed
+        );
+    }
+    let Some(manifest) = PackManifest::load(pack)? else {
+        return Ok(IconMap {
+            names,
+            keywords: HashMap::new(),
+            duplicates,
+        });
+    };
+
+    // 变体及分尺寸图标不单独参与匹配
+    let extra_files = [
+        manifest.variant_files(pack),
+        manifest.size_source_files(pack),
+    ]
+    .concat();
+    names.retain(|_, path| !extra_files.contains(path));
+    duplicates.retain(|duplicate| !extra_files.contains(&duplicate.kept));
+
+    let mut keywords = HashMap::new();
+    for (id, icon) in &manifest.icons {
+        let file = packs
+            .variant
+            .as_deref()
+            .and_then(|variant| icon.variants.get(variant))
+            .or(icon.file.as_ref())
+            .map(|file| pack.join(file))
+            .or_else(|| names.get(&normalize(id)).cloned())
+            .filter(|file| file.is_file());
+        let Some(file) = file else {
+            warn!("Icon of '{id}' not found in {pack:?}");
+            continue;
+        };
+
+        for name in icon.names(id) {
+            names.insert(name, file.clone());
+        }
+        for keyword in &icon.keywords {
+            keywords.insert(normalize(keyword), file.clone());
+        }
+    }
+
+    Ok(IconMap {
+        names,
+        keywords,
+        duplicates,
+    })
+}
+
+/// Variants offered by the packs' manifests
+pub fn pack_variants(packs: &IconPacks) -> Vec<String> {
+    packs
+        .dirs
+        .iter()
+        .filter_map(|pack| {
+            PackManifest::load(pack)
+                .inspect_err(|e| error!("{e}"))
+                .ok()
+                .flatten()
+        })
+        .flat_map(|manifest| manifest.variants())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Icon files of a pack that share a name, only `kept` is used - 同名图标
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateIcons {
+    pub name: String,
+    pub kept: PathBuf,
+    pub shadowed: Vec<PathBuf>,
+}
+
+/// Icon files of a folder by normalized file stem, files sharing a name are settled by `policy`
+fn scan_icons(
+    icons_dir: &Path,
+    policy: &DuplicatePolicy,
+) -> Result<(HashMap<String, PathBuf>, Vec<DuplicateIcons>)> {
+    let pattern = icons_dir
+        .join("**")
+        .join("*.*")
+        .to_str()
+        .map(str::to_owned)
+        .with_context(|| "Failed to get the path")?;
+
+    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for file_path in glob(&pattern)
+        .map_err(|e| anyhow!("Glob failed for {pattern}: {e}"))?
+        .filter_map(Result::ok)
+    {
+        if let Some(name) = file_path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .zip(file_path.extension().and_then(OsStr::to_str))
+            .filter(|(_, ext)| MATCH_ICON_EXT.contains(&ext.to_lowercase().as_str()))
+            .map(|(stem, _)| normalize(stem))
+        {
+            groups.entry(name).or_default().push(file_path);
+        }
+    }
+    fold_size_sources(&mut groups);
+
+    let mut icon_map = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (name, mut files) in groups {
+        files.sort_by(|a, b| policy.compare(icons_dir, a, b));
+        let mut files = files.into_iter();
+        let Some(kept) = files.next() else {
+            continue;
+        };
+        let shadowed = files.collect::<Vec<_>>();
+        if !shadowed.is_empty() {
+            duplicates.push(DuplicateIcons {
+                name: name.clone(),
+                kept: kept.clone(),
+                shadowed,
+            });
+        }
+        icon_map.insert(name, kept);
+    }
+    duplicates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok((icon_map, duplicates))
+}
+
+/// Per-size images like "app-16.png" are frames of "app", not icons of their own
+///
+/// They are folded when "app" exists or there are several sizes of it, the largest size stands
+/// for "app" if there is no such file. "Office-16.png" alone stays an icon.
+fn fold_size_sources(groups: &mut HashMap<String, Vec<PathBuf>>) {
+    let mut sized: HashMap<String, Vec<(u32, PathBuf)>> = HashMap::new();
+    for file in groups.values().flatten() {
+        if let Some((base, size)) = size_suffix(file) {
+            sized
+                .entry(normalize(base))
+                .or_default()
+                .push((size, file.clone()));
+        }
+    }
+
+    for (base, mut files) in sized {
+        if files.len() < 2 && !groups.contains_key(&base) {
+            continue;
+        }
+        for (_, file) in &files {
+            let name = file
+                .file_stem()
+                .map(|stem| normalize(&stem.to_string_lossy()))
+                .unwrap_or_default();
+            if let Some(group) = groups.get_mut(&name) {
+                group.retain(|f| f != file);
+                if group.is_empty() {
+                    groups.remove(&name);
+                }
+            }
+        }
+        files.sort();
+        if let Some((_, largest)) = files.into_iter().max_by_key(|(size, _)| *size) {
+            groups.entry(base).or_insert_with(|| vec![largest]);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchKind {
+    /// Same name - 完全匹配
+    Exact,
+    /// One name contains the other - 包含匹配
+    Partial,
+    /// Similar names - 模糊匹配
+    Fuzzy,
+    /// Decided by `rules.json` - 规则匹配
+    Rule,
+}
+
+impl MatchKind {
+    fn of(link_name: &str, icon_name: &str) -> Self {
+        if link_name == icon_name {
+            MatchKind::Exact
+        } else if link_name.contains(icon_name) || icon_name.contains(link_name) {
+            MatchKind::Partial
+        } else {
+            MatchKind::Fuzzy
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            MatchKind::Exact => t!("MATCH_EXACT").into_owned(),
+            MatchKind::Partial => t!("MATCH_PARTIAL").into_owned(),
+            MatchKind::Fuzzy => t!("MATCH_FUZZY").into_owned(),
+            MatchKind::Rule => t!("MATCH_RULE").into_owned(),
+        }
+    }
+}
+
+/// The icon planned for one shortcut - 匹配计划中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanRow {
+    pub link_name: String,
+    pub link_path: String,
+    pub icon: IconMatch,
+    pub kind: MatchKind,
+    pub alternatives: Vec<IconMatch>,
+    /// Style preset drawn over `icon` when applying, set by a rule
+    #[serde(default)]
+    pub style: Option<IconStyle>,
+    /// Icon group of `icon` drawn by the style, when it is a program or library
+    #[serde(default)]
+    pub icon_index: i32,
+    /// Only accepted rows are written
+    pub accepted: bool,
+}
+
+impl PlanRow {
+    /// Match kind and the key that produced the match, e.g. "Fuzzy · Target name"
+    pub fn match_by(&self) -> String {
+        match self.kind {
+            MatchKind::Rule => self.kind.name(),
+            _ => format!("{} · {}", self.kind.name(), self.icon.key.name()),
+        }
+    }
+
+    /// Swap the chosen icon with one of the alternatives
+    pub fn choose(&mut self, alternative: usize) {
+        if alternative < self.alternatives.len() {
+            std::mem::swap(&mut self.icon, &mut self.alternatives[alternative]);
+            self.kind = MatchKind::of(&self.icon.key_text, &self.icon.name);
+        }
+    }
+}
+
+/// Icons a batch change would write, computed without touching any shortcut - 匹配计划
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatchPlan {
+    pub packs: IconPacks,
+    /// Variants the packs offer
+    #[serde(default)]
+    pub variants: Vec<String>,
+    /// Icons hidden by a file of the same name, for pack maintainers
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateIcons>,
+    pub rows: Vec<PlanRow>,
+}
+
+impl MatchPlan {
+    /// Rules of `rules.json` go first, the other shortcuts are matched by name, target and version info
+    ///
+    /// The best match of the highest-priority pack wins, lower packs only fill the gaps.
+    pub fn build(packs: &IconPacks, source: &ShortcutSource, items: &[LinkProp]) -> Result<Self> {
+        MatchPlan::build_with(packs, source, items, &Rules::load()?, &Journal::load()?)
+    }
+
+    /// Build with the given rules, and the journal that holds the icons before a style
+    fn build_with(
+        packs: &IconPacks,
+        source: &ShortcutSource,
+        items: &[LinkProp],
+        rules: &Rules,
+        journal: &Journal,
+    ) -> Result<Self> {
+        let mut icon_maps = packs
+            .dirs
+            .iter()
+            .map(|pack| load_icon_map(pack, packs))
+            .collect::<Result<Vec<_>>>()?;
+        let duplicates = icon_maps
+            .iter_mut()
+            .flat_map(|icon_map| std::mem::take(&mut icon_map.duplicates))
+            .collect();
+
+        let mut rows = Vec::new();
+        for link_prop in items {
+            if let Some(action) = rules.evaluate(link_prop, source) {
+                if let Some(row) = rule_row(link_prop, action, rules, journal)? {
+                    rows.push(row);
+                }
+                continue;
+            }
+
+            // 名称没有候选时才按目标程序匹配，否则同一浏览器的网页应用都会得到浏览器图标
+            let (name_keys, target_keys): (Vec<_>, Vec<_>) = match_keys(link_prop, packs.pinyin)
+                .into_iter()
+                .partition(|(key, _)| key.is_name());
+            let ranked = icon_maps
+                .iter()
+                .map(|icon_map| {
+                    [
+                        rank_pack(icon_map, &name_keys),
+                        rank_pack(icon_map, &target_keys),
+                    ]
+                })
+                .collect::<Vec<_>>();
+
+            let icon = [&name_keys, &target_keys]
+                .into_iter()
+                .enumerate()
+                .find_map(|(pass, keys)| pass_match(&mut icon_maps, &ranked, pass, keys));
+            let Some(icon) = icon else {
+                continue;
+            };
+
+            // 跳过已在使用该图标的快捷方式
+            let icon_path = converted_icon_path(&icon.path).unwrap_or_else(|_| icon.path.clone());
+            if icon_path == Path::new(&link_prop.target_path)
+                || icon_path == Path::new(&link_prop.icon_path)
+            {
+                continue;
+            }
+
+            // 分数相同时保持图标包的优先级
+            let mut alternatives = ranked
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|m| m.path != icon.path && m.score >= ALTERNATIVE_THRESHOLD)
+                .collect::<Vec<_>>();
+            alternatives.sort_by(|a, b| b.score.total_cmp(&a.score));
+            let mut seen = HashSet::new();
+            alternatives.retain(|m| seen.insert(m.path.clone()));
+            alternatives.truncate(MAX_ALTERNATIVES);
+
+            rows.push(PlanRow {
+                link_name: link_prop.name.clone(),
+                link_path: link_prop.path.clone(),
+                kind: MatchKind::of(&icon.key_text, &icon.name),
+                icon,
+                alternatives,
+                style: None,
+                icon_index: 0,
+                accepted: true,
+            });
+        }
+
+        Ok(MatchPlan {
+            packs: packs.clone(),
+            variants: pack_variants(packs),
+            duplicates,
+            rows,
+        })
+    }
+
+    /// Name of the pack that supplied the icon, `None` for icons set by a rule
+    pub fn pack_name(&self, icon: &IconMatch) -> Option<String> {
+        self.packs
+            .pack_of(&icon.path)
+            .map(|pack| IconPacks::name(pack))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    /// Saved plans can be edited, e.g. set `accepted` to false, and applied later
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Write the accepted rows as one journal batch, returns the changed shortcuts
+    pub fn apply(&self, backends: &[&dyn ShortcutBackend]) -> Result<Vec<String>> {
+        self.apply_to(Journal::begin(backends)?)
+    }
+
+    fn apply_to(&self, mut batch: Batch) -> Result<Vec<String>> {
+        let mut changed = Vec::new();
+
+        for row in self.rows.iter().filter(|row| row.accepted) {
+            let link_path = &row.link_path;
+            let icon_path = match &row.style {
+                Some(style) => render_styled_icon(
+                    &row.icon.path.to_string_lossy(),
+                    row.icon_index,
+                    style,
+                    &styled_icon_name(&row.link_name, &row.link_path, &row.icon.name),
+                )
+                .map(PathBuf::from),
+                None => process_icon(&row.icon.path),
+            };
+            let icon_path = match icon_path {
+                Ok(p) => p.to_string_lossy().into_owned(),
+                Err(e) => {
+                    error!("{e}");
+                    continue;
+                }
+            };
+
+            match batch.set_icon(Path::new(link_path), &icon_path, 0) {
+                Ok(()) => {
+                    info!("{}:\n{link_path}\n{icon_path}", t!("SHORTCUT"));
+                    changed.push(link_path.clone());
+                }
+                Err(e) => error!("Failed to set icon:\n{link_path}\n{icon_path}\n{e}"),
+            }
+        }
+
+        batch.finish()?;
+        Ok(changed)
+    }
+}
+
+/// Names and keywords of a pack ranked together, each icon once with its best score
+fn rank_pack(icon_map: &IconMap, keys: &[(MatchKey, String)]) -> Vec<IconMatch> {
+    let keyword_matches = rank_icons(&icon_map.keywords, keys)
+        .into_iter()
+        .map(|m| IconMatch {
+            score: m.score * KEYWORD_WEIGHT,
+            ..m
+        });
+
+    let mut ranked = rank_icons(&icon_map.names, keys)
+        .into_iter()
+        .chain(keyword_matches)
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut seen = HashSet::new();
+    ranked.retain(|m| seen.insert(m.path.clone()));
+    ranked
+}
+
+/// Exact or best fuzzy match of the highest-priority pack for one pass of keys
+fn pass_match(
+    icon_maps: &mut [IconMap],
+    ranked: &[[Vec<IconMatch>; 2]],
+    pass: usize,
+    keys: &[(MatchKey, String)],
+) -> Option<IconMatch> {
+    icon_maps
+        .iter_mut()
+        .zip(ranked)
+        .find_map(|(icon_map, ranked)| {
+            take_exact_match(icon_map, keys).or_else(|| {
+                ranked[pass]
+                    .first()
+                    .filter(|m| m.score >= MATCH_THRESHOLD)
+                    .cloned()
+            })
+        })
+}
+
+/// Icon named exactly like one of the keys, it is not used for other shortcuts - 完全匹配
+fn take_exact_match(icon_map: &mut IconMap, keys: &[(MatchKey, String)]) -> Option<IconMatch> {
+    let (key, key_text, path) = keys.iter().find_map(|(key, key_text)| {
+        icon_map
+            .names
+            .get(key_text)
+            .map(|path| (*key, key_text.clone(), path.clone()))
+    })?;
+
+    // 同一图标的别名一并移除
+    icon_map.names.retain(|_, p| *p != path);
+    icon_map.keywords.retain(|_, p| *p != path);
+
+    Some(IconMatch {
+        name: key_text.clone(),
+        path,
+        score: 1.0,
+        key,
+        key_text,
+    })
+}
+
+/// Row for a shortcut decided by a rule, `None` when it is skipped or already uses the icon
+fn rule_row(
+    link_prop: &LinkProp,
+    action: &RuleAction,
+    rules: &Rules,
+    journal: &Journal,
+) -> Result<Option<PlanRow>> {
+    let (icon, style, icon_index) = match action {
+        RuleAction::Skip => return Ok(None),
+        RuleAction::Icon(path) => {
+            let path = PathBuf::from(ManageLinkProp::convert_env_to_path(&path.to_string_lossy()));
+            let icon_path = converted_icon_path(&path).unwrap_or_else(|_| path.clone());
+            if icon_path == Path::new(&link_prop.icon_path) {
+                return Ok(None);
+            }
+            let name = path
+                .file_stem()
+                .map(|n| normalize(&n.to_string_lossy()))
+                .unwrap_or_default();
+            let icon = IconMatch {
+                name,
+                path,
+                score: 1.0,
+                ..IconMatch::default()
+            };
+            (icon, None, 0)
+        }
+        RuleAction::Style(preset) => {
+            // 已应用过该样式的快捷方式不再重复绘制
+            let styled_path = ensure_local_app_folder_exists()?
+                .join(ICONS_DIR)
+                .join(format!(
+                    "{}.ico",
+                    styled_icon_name(&link_prop.name, &link_prop.path, preset)
+                ));
+            if styled_path == Path::new(&link_prop.icon_path) {
+                return Ok(None);
+            }
+
+            let (path, icon_index) = match is_styled_icon(&link_prop.icon_path, &link_prop.path) {
+                // 换用其他样式时从未加样式的图标重新绘制，而不是叠加在旧样式上
+                true => match unstyled_icon(journal, &link_prop.path) {
+                    Some(unstyled) => unstyled,
+                    None => {
+                        warn!(
+                            "The icon before the style was not found in the journal: {}",
+                            link_prop.path
+                        );
+                        return Ok(None);
+                    }
+                },
+                // 无自定义图标时绘制快捷方式本身的图标
+                false => match link_prop.icon_path.is_empty() {
+                    true => (PathBuf::from(&link_prop.path), 0),
+                    false => (
+                        PathBuf::from(&link_prop.icon_path),
+                        link_prop.icon_index.parse().unwrap_or(0),
+                    ),
+                },
+            };
+            let icon = IconMatch {
+                name: preset.clone(),
+                path,
+                score: 1.0,
+                ..IconMatch::default()
+            };
+            (icon, rules.preset(preset).cloned(), icon_index)
+        }
+    };
+
+    Ok(Some(PlanRow {
+        link_name: link_prop.name.clone(),
+        link_path: link_prop.path.clone(),
+        icon,
+        kind: MatchKind::Rule,
+        alternatives: Vec::new(),
+        style,
+        icon_index,
+        accepted: true,
+    }))
+}
+
+/// Hash of the shortcut path, so equally named shortcuts get their own styled icons
+fn link_path_hash(link_path: &str) -> String {
+    format!("{:016x}", xxh3_64(link_path.to_lowercase().as_bytes()))
+}
+
+/// File stem of a shortcut's icon drawn with a style preset
+fn styled_icon_name(link_name: &str, link_path: &str, preset: &str) -> String {
+    format!("{link_name} ({preset})-{}", link_path_hash(link_path))
+}
+
+/// Whether the icon was drawn with a style preset for this shortcut
+fn is_styled_icon(icon_path: &str, link_path: &str) -> bool {
+    Path::new(icon_path).file_stem().is_some_and(|stem| {
+        stem.to_string_lossy()
+            .ends_with(&format!(")-{}", link_path_hash(link_path)))
+    })
+}
+
+/// Icon location the shortcut had before its icons were styled, the shortcut itself if it had none
+fn unstyled_icon(journal: &Journal, link_path: &str) -> Option<(PathBuf, i32)> {
+    let old = journal
+        .applied_changes(link_path)
+        .map(|entry| &entry.old)
+        .find(|old| !is_styled_icon(&old.path, link_path))?;
+    match old.path.is_empty() {
+        true => Some((PathBuf::from(link_path), 0)),
+        false => Some((
+            PathBuf::from(ManageLinkProp::convert_env_to_path(&old.path)),
+            old.index,
+        )),
+    }
+}
+
+/// One line per row for the CLI
+impl fmt::Display for MatchPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.rows {
+            write!(
+                f,
+                "[{}] {} -> {}{} ({}, {:.2})",
+                if row.accepted { "x" } else { " " },
+                row.link_name,
+                row.icon.path.display(),
+                self.pack_name(&row.icon)
+                    .map(|pack| format!(" [{pack}]"))
+                    .unwrap_or_default(),
+                row.match_by(),
+                row.icon.score
+            )?;
+            if !row.alternatives.is_empty() {
+                let alternatives = row
+                    .alternatives
+                    .iter()
+                    .map(|m| {
+                        let pack = self.pack_name(m).unwrap_or_default();
+                        format!("{} {:.2} ({}, {pack})", m.name, m.score, m.key.name())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "\n    {}: {alternatives}", t!("MATCH_ALTERNATIVES"))?;
+            }
+            writeln!(f)?;
+        }
+        // 供图标包维护者清理
+        if !self.duplicates.is_empty() {
+            writeln!(f, "\n{}:", t!("DUPLICATE_ICONS"))?;
+            for duplicate in &self.duplicates {
+                writeln!(f, "{} -> {}", duplicate.name, duplicate.kept.display())?;
+                for shadowed in &duplicate.shadowed {
+                    writeln!(f, "    - {}", shadowed.display())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::{backend::memory::MemoryBackend, journal::JOURNAL_FILE};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("linkecho-plan-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A pack folder with empty icon files, matching only reads their names
+    fn pack(dir: &Path, name: &str, icons: &[&str]) -> PathBuf {
+        let pack = dir.join(name);
+        std::fs::create_dir_all(&pack).unwrap();
+        for icon in icons {
+            std::fs::write(pack.join(icon), b"").unwrap();
+        }
+        pack
+    }
+
+    fn link(name: &str, path: &str) -> LinkProp {
+        LinkProp {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            ..LinkProp::default()
+        }
+    }
+
+    fn row(link_path: &str, icon_path: &str, accepted: bool) -> PlanRow {
+        PlanRow {
+            link_name: Path::new(link_path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            link_path: link_path.to_owned(),
+            icon: IconMatch {
+                path: PathBuf::from(icon_path),
+                score: 1.0,
+                ..IconMatch::default()
+            },
+            kind: MatchKind::Exact,
+            alternatives: Vec::new(),
+            style: None,
+            icon_index: 0,
+            accepted,
+        }
+    }
+
+    #[test]
+    fn higher_pack_wins_and_lower_packs_fill_gaps() {
+        let dir = temp_dir("build");
+        let high = pack(&dir, "high", &["Chrome.ico", "Firefox.ico"]);
+        let low = pack(&dir, "low", &["chrome.ico", "Steam.ico"]);
+        let packs = IconPacks {
+            dirs: vec![high.clone(), low.clone()],
+            ..IconPacks::default()
+        };
+        let items = [
+            link("Chrome", "/links/Chrome.lnk"),
+            link("Steam", "/links/Steam.lnk"),
+            // 已在使用该图标
+            LinkProp {
+                icon_path: high.join("Firefox.ico").to_string_lossy().into_owned(),
+                ..link("Firefox", "/links/Firefox.lnk")
+            },
+            link("Zzz Unknown", "/links/Zzz Unknown.lnk"),
+        ];
+
+        let plan = MatchPlan::build_with(
+            &packs,
+            &ShortcutSource::Other(PathBuf::from("/links")),
+            &items,
+            &Rules::default(),
+            &Journal::default(),
+        )
+        .unwrap();
+
+        let rows = plan
+            .rows
+            .iter()
+            .map(|row| (row.link_name.as_str(), row.icon.path.clone(), row.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                ("Chrome", high.join("Chrome.ico"), MatchKind::Exact),
+                ("Steam", low.join("Steam.ico"), MatchKind::Exact),
+            ]
+        );
+        assert_eq!(plan.pack_name(&plan.rows[1].icon).as_deref(), Some("low"));
+        assert!(plan.duplicates.is_empty());
+    }
+
+    #[test]
+    fn exact_match_is_taken_once() {
+        let chrome = PathBuf::from("/icons/chrome.ico");
+        let mut icon_map = IconMap {
+            names: HashMap::from([
+                ("chrome".to_owned(), chrome.clone()),
+                ("google chrome".to_owned(), chrome.clone()),
+                ("steam".to_owned(), PathBuf::from("/icons/steam.ico")),
+            ]),
+            keywords: HashMap::from([("browser".to_owned(), chrome.clone())]),
+            duplicates: Vec::new(),
+        };
+        let keys = [
+            (MatchKey::Name, "google chrome".to_owned()),
+            (MatchKey::TargetName, "chrome".to_owned()),
+        ];
+
+        let icon = take_exact_match(&mut icon_map, &keys).unwrap();
+        assert_eq!(icon.path, chrome);
+        assert_eq!(
+            (icon.key, icon.key_text.as_str()),
+            (MatchKey::Name, "google chrome")
+        );
+        assert_eq!(icon.score, 1.0);
+        // 别名与关键词一并移除，其他快捷方式不再得到该图标
+        assert_eq!(icon_map.names.len(), 1);
+        assert!(icon_map.keywords.is_empty());
+        assert_eq!(take_exact_match(&mut icon_map, &keys), None);
+        assert_eq!(
+            take_exact_match(&mut icon_map, &[(MatchKey::Name, "chrome beta".to_owned())]),
+            None
+        );
+    }
+
+    #[test]
+    fn only_accepted_rows_are_applied() {
+        let backend = MemoryBackend::default();
+        backend.add("/links/Chrome.lnk", None);
+        backend.add("/links/Steam.lnk", Some(("/steam/steam.exe", 0)));
+        let backends: [&dyn ShortcutBackend; 1] = [&backend];
+        let dir = temp_dir("apply");
+
+        let plan = MatchPlan {
+            rows: vec![
+                row("/links/Chrome.lnk", "/icons/chrome.ico", true),
+                row("/links/Steam.lnk", "/icons/steam.ico", false),
+                // 不存在的快捷方式被跳过
+                row("/links/Missing.lnk", "/icons/missing.ico", true),
+            ],
+            ..MatchPlan::default()
+        };
+        let changed = plan.apply_to(Batch::new(dir.clone(), &backends)).unwrap();
+
+        assert_eq!(changed, ["/links/Chrome.lnk"]);
+        let icon = |path: &str| backend.icon_location(Path::new(path)).unwrap();
+        assert_eq!(
+            icon("/links/Chrome.lnk"),
+            Some(("/icons/chrome.ico".to_owned(), 0))
+        );
+        assert_eq!(
+            icon("/links/Steam.lnk"),
+            Some(("/steam/steam.exe".to_owned(), 0))
+        );
+        let journal = Journal::open(&dir.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(
+            journal.link_paths().collect::<Vec<_>>(),
+            ["/links/Chrome.lnk"]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use super::{
+    info::ManageLinkProp,
+    list::{LinkProp, ShortcutSource},
+};
+use crate::{image::customize::IconStyle, utils::ensure_local_app_folder_exists};
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde::Deserialize;
+
+const RULES_FILE: &str = "rules.json";
+
+/// Where a shortcut was listed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSource {
+    Desktop,
+    StartMenu,
+    Applications,
+    Other,
+}
+
+impl RuleSource {
+    fn of(source: &ShortcutSource) -> Self {
+        match source {
+            ShortcutSource::Desktop => RuleSource::Desktop,
+            ShortcutSource::StartMenu => RuleSource::StartMenu,
+            ShortcutSource::Applications => RuleSource::Applications,
+            ShortcutSource::Other(_) => RuleSource::Other,
+        }
+    }
+}
+
+/// Every given condition has to match
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleCondition {
+    /// Regex on the shortcut name
+    pub name: Option<String>,
+    /// Glob on the target path, case-insensitive
+    pub target: Option<String>,
+    pub target_ext: Option<String>,
+    /// Regex on the arguments
+    pub arguments: Option<String>,
+    pub source: Option<RuleSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Leave the shortcut unchanged
+    Skip,
+    /// Use this icon file
+    Icon(PathBuf),
+    /// Draw the shortcut's current icon with a preset of `presets`
+    Style(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub when: RuleCondition,
+    pub then: RuleAction,
+}
+
+/// Content of `rules.json`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RulesFile {
+    presets: HashMap<String, IconStyle>,
+    rules: Vec<Rule>,
+}
+
+/// A rule with its patterns compiled
+struct CompiledRule {
+    name: Option<Regex>,
+    target: Option<Pattern>,
+    target_ext: Option<String>,
+    arguments: Option<Regex>,
+    source: Option<RuleSource>,
+    action: RuleAction,
+}
+
+impl CompiledRule {
+    fn compile(index: usize, rule: Rule) -> Result<Self> {
+        let context = || format!("Invalid rule #{}", index + 1);
+        let RuleCondition {
+            name,
+            target,
+            target_ext,
+            arguments,
+            source,
+        } = rule.when;
+
+        Ok(CompiledRule {
+            name: name
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .with_context(context)?,
+            target: target
+                .map(|t| Pattern::new(&ManageLinkProp::convert_env_to_path(&t)))
+                .transpose()
+                .with_context(context)?,
+            target_ext: target_ext.map(|ext| ext.trim_start_matches('.').to_lowercase()),
+            arguments: arguments
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .with_context(context)?,
+            source,
+            action: rule.then,
+        })
+    }
+
+    fn matches(&self, link_prop: &LinkProp, source: RuleSource) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::default()
+        };
+
+        self.name
+            .as_ref()
+            .is_none_or(|r| r.is_match(&link_prop.name))
+            && self
+                .target
+                .as_ref()
+                .is_none_or(|p| p.matches_with(&link_prop.target_path, options))
+            && self
+                .target_ext
+                .as_ref()
+                .is_none_or(|ext| ext.eq_ignore_ascii_case(&link_prop.target_ext))
+            && self
+                .arguments
+                .as_ref()
+                .is_none_or(|r| r.is_match(&link_prop.arguments))
+            && self.source.is_none_or(|s| s == source)
+    }
+}
+
+/// Ordered matching rules, saved as `rules.json` in the local app folder - 自定义匹配规则
+///
+/// The first rule whose conditions all match decides, shortcuts without one go to the name matcher:
+///
+/// ```json
+/// {
+///   "presets": { "rounded": { "icon_scaling": 80, "icon_borders_radius": 40 } },
+///   "rules": [
+///     { "when": { "name": "^Uninstall" }, "then": "skip" },
+///     { "when": { "target": "C:\\Program Files\\JetBrains\\**" }, "then": { "icon": "D:\\Icons\\jetbrains.ico" } },
+///     { "when": { "target_ext": "url", "source": "desktop" }, "then": { "style": "rounded" } }
+///   ]
+/// }
+/// ```
+#[derive(Default)]
+pub struct Rules {
+    presets: HashMap<String, IconStyle>,
+    rules: Vec<CompiledRule>,
+}
+
+impl Rules {
+    pub fn path() -> Result<PathBuf> {
+        Ok(ensure_local_app_folder_exists()?.join(RULES_FILE))
+    }
+
+    /// No rules file means no rules
+    pub fn load() -> Result<Self> {
+        let path = Rules::path()?;
+        if !path.is_file() {
+            return Ok(Rules::default());
+        }
+        Rules::load_from(&path)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        let file: RulesFile =
+            serde_json::from_slice(&data).with_context(|| format!("Failed to parse {path:?}"))?;
+
+        let rules = file
+            .rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| CompiledRule::compile(index, rule))
+            .collect::<Result<Vec<_>>>()?;
+
+        // 样式预设须在'presets'中定义
+        if let Some(preset) = rules.iter().find_map(|rule| match &rule.action {
+            RuleAction::Style(preset) if !file.presets.contains_key(preset) => Some(preset),
+            _ => None,
+        }) {
+            bail!("Unknown style preset in {path:?}: {preset}");
+        }
+
+        Ok(Rules {
+            presets: file.presets,
+            rules,
+        })
+    }
+
+    /// Action of the first matching rule
+    pub fn evaluate(&self, link_prop: &LinkProp, source: &ShortcutSource) -> Option<&RuleAction> {
+        let source = RuleSource::of(source);
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(link_prop, source))
+            .map(|rule| &rule.action)
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&IconStyle> {
+        self.presets.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, json: &str) -> Result<Rules> {
+        let path = std::env::temp_dir().join(format!("linkecho-rules-{name}.json"));
+        std::fs::write(&path, json).unwrap();
+        Rules::load_from(&path)
+    }
+
+    fn link(name: &str, target_path: &str, arguments: &str) -> LinkProp {
+        LinkProp {
+            name: name.to_owned(),
+            target_path: target_path.to_owned(),
+            target_ext: Path::new(target_path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            arguments: arguments.to_owned(),
+            ..LinkProp::default()
+        }
+    }
+
+    const RULES: &str = r#"{
+        "presets": { "rounded": { "icon_scaling": 80, "icon_borders_radius": 40 } },
+        "rules": [
+            { "when": { "name": "^Uninstall" }, "then": "skip" },
+            { "when": { "target": "/opt/JetBrains/**", "arguments": "--safe" }, "then": "skip" },
+            { "when": { "target": "/opt/JETBRAINS/**" }, "then": { "icon": "/icons/jetbrains.ico" } },
+            { "when": { "target_ext": ".URL", "source": "desktop" }, "then": { "style": "rounded" } },
+            { "when": { "name": "IDEA" }, "then": { "icon": "/icons/idea.ico" } }
+        ]
+    }"#;
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let rules = load("precedence", RULES).unwrap();
+        let other = ShortcutSource::Other(PathBuf::from("/links"));
+        let evaluate = |link_prop: &LinkProp, source: &ShortcutSource| {
+            rules.evaluate(link_prop, source).cloned()
+        };
+
+        let idea = link("IntelliJ IDEA", "/opt/jetbrains/idea/bin/idea.sh", "");
+        // 目标路径不区分大小写，且先于名称规则
+        assert_eq!(
+            evaluate(&idea, &other),
+            Some(RuleAction::Icon(PathBuf::from("/icons/jetbrains.ico")))
+        );
+        let safe_mode = LinkProp {
+            arguments: "--safe".to_owned(),
+            ..idea.clone()
+        };
+        assert_eq!(evaluate(&safe_mode, &other), Some(RuleAction::Skip));
+        let uninstall = LinkProp {
+            name: "Uninstall IDEA".to_owned(),
+            ..idea
+        };
+        assert_eq!(evaluate(&uninstall, &other), Some(RuleAction::Skip));
+
+        let web = link("Docs", "/links/docs.url", "");
+        assert_eq!(
+            evaluate(&web, &ShortcutSource::Desktop),
+            Some(RuleAction::Style("rounded".to_owned()))
+        );
+        assert_eq!(evaluate(&web, &other), None);
+        assert!(rules.preset("rounded").is_some());
+    }
+
+    #[test]
+    fn shortcut_matching_no_rule_is_left_to_the_matcher() {
+        let rules = load("no_match", RULES).unwrap();
+        let firefox = link("Firefox", "/usr/bin/firefox", "--new-window");
+        assert_eq!(rules.evaluate(&firefox, &ShortcutSource::Desktop), None);
+
+        let empty = load("empty", "{}").unwrap();
+        assert_eq!(empty.evaluate(&firefox, &ShortcutSource::Desktop), None);
+    }
+
+    #[test]
+    fn invalid_rules_are_errors() {
+        for (name, json) in [
+            (
+                "unknown_preset",
+                r#"{ "rules": [{ "when": {}, "then": { "style": "flat" } }] }"#,
+            ),
+            (
+                "invalid_regex",
+                r#"{ "rules": [{ "when": { "name": "(" }, "then": "skip" }] }"#,
+            ),
+            (
+                "unknown_field",
+                r#"{ "rules": [{ "when": { "title": "a" }, "then": "skip" }] }"#,
+            ),
+            (
+                "unknown_action",
+                r#"{ "rules": [{ "when": {}, "then": "delete" }] }"#,
+            ),
+            ("not_json", "rules"),
+        ] {
+            assert!(load(name, json).is_err(), "{name}");
+        }
+    }
+}
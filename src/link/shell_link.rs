@@ -0,0 +1,1063 @@
+// Shell Link Binary File Format (MS-SHLLINK)
+// https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink
+//
+// 纯 Rust 解析 .lnk 文件，不依赖 COM 接口
+
+use super::ansi;
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, ensure};
+
+const HEADER_SIZE: usize = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+// {20D04FE0-3AEA-1069-A2D8-08002B30309D} - This PC - 此电脑
+const MY_COMPUTER_CLSID: [u8; 16] = [
+    0xE0, 0x4F, 0xD0, 0x20, 0xEA, 0x3A, 0x69, 0x10, 0xA2, 0xD8, 0x08, 0x00, 0x2B, 0x30, 0x30, 0x9D,
+];
+
+// LinkFlags
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0000_0001;
+const HAS_LINK_INFO: u32 = 0x0000_0002;
+const HAS_NAME: u32 = 0x0000_0004;
+const HAS_RELATIVE_PATH: u32 = 0x0000_0008;
+const HAS_WORKING_DIR: u32 = 0x0000_0010;
+const HAS_ARGUMENTS: u32 = 0x0000_0020;
+const HAS_ICON_LOCATION: u32 = 0x0000_0040;
+const IS_UNICODE: u32 = 0x0000_0080;
+const HAS_EXP_STRING: u32 = 0x0000_0200;
+const HAS_EXP_ICON: u32 = 0x0000_4000;
+
+const FILE_ATTRIBUTE_NORMAL: u32 = 0x0000_0080;
+const SW_SHOWNORMAL: u32 = 0x0000_0001;
+
+// LinkInfoFlags
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x0000_0001;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 0x0000_0002;
+
+// ExtraData block signatures
+const ENVIRONMENT_VARIABLE_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_BLOCK: u32 = 0xA000_0007;
+
+/// StringData 中各字段的顺序与 LinkFlags 对应
+const STRING_FLAGS: [u32; 5] = [
+    HAS_NAME,
+    HAS_RELATIVE_PATH,
+    HAS_WORKING_DIR,
+    HAS_ARGUMENTS,
+    HAS_ICON_LOCATION,
+];
+
+#[allow(unused)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringField {
+    Name,
+    RelativePath,
+    WorkingDir,
+    Arguments,
+    IconLocation,
+}
+
+impl StringField {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraDataBlock {
+    pub signature: u32,
+    /// Block content after `BlockSize` and `BlockSignature`
+    pub data: Vec<u8>,
+}
+
+/// Every section is kept as raw bytes so that nothing we don't understand is lost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellLink {
+    header: [u8; HEADER_SIZE],
+    /// LinkTargetIDList without the leading `IDListSize`
+    id_list: Option<Vec<u8>>,
+    /// LinkInfo including `LinkInfoSize`
+    link_info: Option<Vec<u8>>,
+    /// StringData characters without the leading `CountCharacters`
+    strings: [Option<Vec<u8>>; 5],
+    extra_data: Vec<ExtraDataBlock>,
+    /// TerminalBlock and anything after it
+    tail: Vec<u8>,
+}
+
+impl ShellLink {
+    /// A new Unicode shortcut pointing at a local path or a network share
+    ///
+    /// Targets containing `%`, or on a drive of unknown type, are stored in an
+    /// EnvironmentVariableDataBlock - 含环境变量或驱动器类型未知的路径写入扩展块
+    pub fn new(target_path: &str) -> Self {
+        let mut header = [0u8; HEADER_SIZE];
+        header[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        header[4..20].copy_from_slice(&LINK_CLSID);
+        header[0x18..0x1C].copy_from_slice(&FILE_ATTRIBUTE_NORMAL.to_le_bytes());
+        header[0x3C..0x40].copy_from_slice(&SW_SHOWNORMAL.to_le_bytes());
+
+        let mut shell_link = ShellLink {
+            header,
+            id_list: None,
+            link_info: None,
+            strings: Default::default(),
+            extra_data: Vec::new(),
+            tail: vec![0; 4],
+        };
+        shell_link.set_flag(IS_UNICODE, true);
+
+        let link_info = match target_path.contains('%') {
+            true => None,
+            false => target_link_info(target_path),
+        };
+        match link_info {
+            Some(link_info) => {
+                shell_link.link_info = Some(link_info);
+                shell_link.set_flag(HAS_LINK_INFO, true);
+            }
+            None => {
+                shell_link.extra_data.push(ExtraDataBlock {
+                    signature: ENVIRONMENT_VARIABLE_BLOCK,
+                    data: environment_block_data(target_path),
+                });
+                shell_link.set_flag(HAS_EXP_STRING, true);
+            }
+        }
+
+        shell_link
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read the shortcut: {path:?}"))?;
+        ShellLink::parse(&data).with_context(|| format!("Failed to parse the shortcut: {path:?}"))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = self.to_bytes()?;
+        // Never write a shortcut that we can't read back - 不写入无法重新解析的快捷方式
+        ShellLink::parse(&data)
+            .with_context(|| format!("Refuse to write an invalid shortcut: {path:?}"))?;
+        std::fs::write(path, data)
+            .with_context(|| format!("Failed to write the shortcut: {path:?}"))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+
+        let header: [u8; HEADER_SIZE] = reader.take(HEADER_SIZE)?.try_into()?;
+        ensure!(
+            read_u32(&header, 0)? as usize == HEADER_SIZE,
+            "Invalid shell link header size"
+        );
+        ensure!(header[4..20] == LINK_CLSID, "Invalid shell link CLSID");
+
+        let flags = read_u32(&header, 0x14)?;
+
+        let id_list = if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let size = reader.u16()? as usize;
+            Some(reader.take(size)?.to_vec())
+        } else {
+            None
+        };
+
+        let link_info = if flags & HAS_LINK_INFO != 0 {
+            let size = read_u32(reader.peek(4)?, 0)? as usize;
+            ensure!(size >= 4, "Invalid LinkInfo size: {size}");
+            Some(reader.take(size)?.to_vec())
+        } else {
+            None
+        };
+
+        let char_size = if flags & IS_UNICODE != 0 { 2 } else { 1 };
+        let mut strings: [Option<Vec<u8>>; 5] = Default::default();
+        for (string, flag) in strings.iter_mut().zip(STRING_FLAGS) {
+            if flags & flag != 0 {
+                let count = reader.u16()? as usize;
+                *string = Some(reader.take(count * char_size)?.to_vec());
+            }
+        }
+
+        let mut extra_data = Vec::new();
+        let tail = loop {
+            let rest = reader.rest();
+            // TerminalBlock: BlockSize < 0x04, 截断的文件同样视为结束
+            if rest.len() < 8 || (read_u32(rest, 0)? as usize) < 8 {
+                break rest.to_vec();
+            }
+            let size = read_u32(rest, 0)? as usize;
+            let block = reader.take(size)?;
+            extra_data.push(ExtraDataBlock {
+                signature: read_u32(block, 4)?,
+                data: block[8..].to_vec(),
+            });
+        };
+
+        Ok(ShellLink {
+            header,
+            id_list,
+            link_info,
+            strings,
+            extra_data,
+            tail,
+        })
+    }
+
+    /// Unchanged sections are written back byte-for-byte - 未修改的部分按原字节写回
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = self.header.to_vec();
+
+        if let Some(id_list) = &self.id_list {
+            let size = u16::try_from(id_list.len())
+                .map_err(|_| anyhow!("LinkTargetIDList is too long: {} bytes", id_list.len()))?;
+            data.extend(size.to_le_bytes());
+            data.extend(id_list);
+        }
+
+        if let Some(link_info) = &self.link_info {
+            data.extend(link_info);
+        }
+
+        let char_size = if self.is_unicode() { 2 } else { 1 };
+        for raw in self.strings.iter().flatten() {
+            let count = u16::try_from(raw.len() / char_size)
+                .map_err(|_| anyhow!("String is too long: {} characters", raw.len() / char_size))?;
+            data.extend(count.to_le_bytes());
+            data.extend(raw);
+        }
+
+        for block in &self.extra_data {
+            data.extend(((block.data.len() + 8) as u32).to_le_bytes());
+            data.extend(block.signature.to_le_bytes());
+            data.extend(&block.data);
+        }
+
+        data.extend(&self.tail);
+        Ok(data)
+    }
+
+    pub fn flags(&self) -> u32 {
+        u32::from_le_bytes(self.header[0x14..0x18].try_into().unwrap())
+    }
+
+    fn set_flag(&mut self, flag: u32, enabled: bool) {
+        let flags = match enabled {
+            true => self.flags() | flag,
+            false => self.flags() & !flag,
+        };
+        self.header[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+    }
+
+    pub fn icon_index(&self) -> i32 {
+        i32::from_le_bytes(self.header[0x38..0x3C].try_into().unwrap())
+    }
+
+    pub fn is_unicode(&self) -> bool {
+        self.flags() & IS_UNICODE != 0
+    }
+
+    pub fn string(&self, field: StringField) -> Option<String> {
+        self.strings[field.index()]
+            .as_deref()
+            .map(|raw| decode_string(raw, self.is_unicode()))
+    }
+
+    /// `None` or an empty value removes the string from StringData
+    ///
+    /// An ANSI link is converted to Unicode when the value is outside the code page.
+    /// The conversion is refused if the other strings can't be decoded - 无法解码其他字符串时拒绝转换
+    pub fn set_string(&mut self, field: StringField, value: Option<&str>) -> Result<()> {
+        let value = value.filter(|v| !v.is_empty());
+
+        let raw = match value {
+            None => None,
+            Some(v) if self.is_unicode() => Some(encode_string(v, true)),
+            Some(v) => match ansi::encode(v) {
+                Some(raw) => Some(raw),
+                None => {
+                    self.convert_to_unicode()?;
+                    Some(encode_string(v, true))
+                }
+            },
+        };
+
+        let char_size = if self.is_unicode() { 2 } else { 1 };
+        if let Some(raw) = &raw {
+            ensure!(
+                raw.len() / char_size <= u16::MAX as usize,
+                "{field:?} is too long: {} characters",
+                raw.len() / char_size
+            );
+        }
+
+        self.strings[field.index()] = raw;
+        self.set_flag(STRING_FLAGS[field.index()], value.is_some());
+        Ok(())
+    }
+
+    /// Re-encode every ANSI string as UTF-16
+    fn convert_to_unicode(&mut self) -> Result<()> {
+        let mut strings = self.strings.clone();
+        for raw in strings.iter_mut().flatten() {
+            let value = ansi::decode(raw)
+                .context("Refuse to convert strings of an unknown ANSI code page")?;
+            *raw = encode_string(&value, true);
+        }
+        self.strings = strings;
+        self.set_flag(IS_UNICODE, true);
+        Ok(())
+    }
+
+    /// NAME_STRING, shown as "Comment" in the shortcut's properties - 备注
+    pub fn description(&self) -> Option<String> {
+        self.string(StringField::Name)
+    }
+
+    pub fn hotkey(&self) -> u16 {
+        u16::from_le_bytes(self.header[0x40..0x42].try_into().unwrap())
+    }
+
+    pub fn set_hotkey(&mut self, hotkey: u16) {
+        self.header[0x40..0x42].copy_from_slice(&hotkey.to_le_bytes());
+    }
+
+    pub fn show_command(&self) -> u32 {
+        u32::from_le_bytes(self.header[0x3C..0x40].try_into().unwrap())
+    }
+
+    pub fn set_show_command(&mut self, show_command: u32) {
+        self.header[0x3C..0x40].copy_from_slice(&show_command.to_le_bytes());
+    }
+
+    pub fn working_dir(&self) -> Option<String> {
+        self.string(StringField::WorkingDir)
+    }
+
+    pub fn arguments(&self) -> Option<String> {
+        self.string(StringField::Arguments)
+    }
+
+    /// Raw icon location, environment variables are not expanded - 原始图标位置，未展开环境变量
+    pub fn icon_location(&self) -> Option<(String, i32)> {
+        let icon_path = (self.flags() & HAS_EXP_ICON != 0)
+            .then(|| self.environment_target(ICON_ENVIRONMENT_BLOCK))
+            .flatten()
+            .or_else(|| self.string(StringField::IconLocation))
+            .filter(|p| !p.is_empty())?;
+
+        Some((icon_path, self.icon_index()))
+    }
+
+    /// Rewrite the IconLocation string, the icon index and the IconEnvironmentDataBlock
+    ///
+    /// An empty `icon_path` clears the icon location - 空路径表示清除图标位置
+    pub fn set_icon_location(&mut self, icon_path: &str, icon_index: i32) -> Result<()> {
+        self.set_string(StringField::IconLocation, Some(icon_path))?;
+        self.header[0x38..0x3C].copy_from_slice(&icon_index.to_le_bytes());
+
+        let has_env = icon_path.contains('%');
+        self.extra_data
+            .retain(|b| has_env || b.signature != ICON_ENVIRONMENT_BLOCK);
+        if has_env {
+            let data = environment_block_data(icon_path);
+            match self
+                .extra_data
+                .iter_mut()
+                .find(|b| b.signature == ICON_ENVIRONMENT_BLOCK)
+            {
+                Some(block) => block.data = data,
+                None => self.extra_data.push(ExtraDataBlock {
+                    signature: ICON_ENVIRONMENT_BLOCK,
+                    data,
+                }),
+            }
+        }
+        self.set_flag(HAS_EXP_ICON, has_env);
+        Ok(())
+    }
+
+    /// Raw target path, similar to `IShellLink::GetPath(SLGP_RAWPATH)`
+    ///
+    /// Returns `None` for targets outside the file system (UWP, shell folders...)
+    pub fn target_path(&self) -> Option<String> {
+        (self.flags() & HAS_EXP_STRING != 0)
+            .then(|| self.environment_target(ENVIRONMENT_VARIABLE_BLOCK))
+            .flatten()
+            .or_else(|| self.link_info_path())
+            .or_else(|| self.id_list_path())
+            .filter(|p| !p.is_empty())
+    }
+
+    fn extra_data_block(&self, signature: u32) -> Option<&ExtraDataBlock> {
+        self.extra_data.iter().find(|b| b.signature == signature)
+    }
+
+    /// EnvironmentVariableDataBlock / IconEnvironmentDataBlock:
+    /// TargetAnsi (260 bytes) + TargetUnicode (520 bytes)
+    fn environment_target(&self, signature: u32) -> Option<String> {
+        let data = &self.extra_data_block(signature)?.data;
+        let unicode = data
+            .get(260..780)
+            .map(read_utf16_cstr)
+            .filter(|s| !s.is_empty());
+        unicode.or_else(|| {
+            data.get(..260)
+                .map(read_ansi_cstr)
+                .filter(|s| !s.is_empty())
+        })
+    }
+
+    fn link_info_path(&self) -> Option<String> {
+        let info = self.link_info.as_deref()?;
+        let header_size = read_u32(info, 4).ok()?;
+        let info_flags = read_u32(info, 8).ok()?;
+        let has_unicode = header_size >= 0x24;
+
+        let common_path_suffix = match has_unicode {
+            true => read_offset_utf16(info, 0x20),
+            false => None,
+        }
+        .or_else(|| read_offset_ansi(info, 0x18))
+        .unwrap_or_default();
+
+        let base = if info_flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+            match has_unicode {
+                true => read_offset_utf16(info, 0x1C),
+                false => None,
+            }
+            .or_else(|| read_offset_ansi(info, 0x10))?
+        } else if info_flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 {
+            let offset = read_u32(info, 0x14).ok()? as usize;
+            let network = info.get(offset..)?;
+            let net_name_offset = read_u32(network, 8).ok()?;
+            match net_name_offset > 0x14 {
+                true => read_offset_utf16(network, 0x14),
+                false => None,
+            }
+            .or_else(|| read_offset_ansi(network, 8))?
+        } else {
+            return None;
+        };
+
+        Some(join_path(&base, &common_path_suffix))
+    }
+
+    /// Resolve file system items of the LinkTargetIDList - 解析 IDList 中的文件系统项
+    fn id_list_path(&self) -> Option<String> {
+        let id_list = self.id_list.as_deref()?;
+        let mut reader = Reader::new(id_list);
+        let mut path = String::new();
+
+        loop {
+            let size = reader.u16().ok()? as usize;
+            if size == 0 {
+                break;
+            }
+            let item = reader.take(size.checked_sub(2)?).ok()?;
+            let class_type = *item.first()?;
+
+            match class_type & 0x70 {
+                // Root folder, only "This PC" leads to the file system
+                0x10 => {
+                    if item.get(2..18)? != MY_COMPUTER_CLSID {
+                        return None;
+                    }
+                }
+                // Volume: "C:\"
+                0x20 => path = read_ansi_cstr(item.get(1..)?),
+                // File entry
+                0x30 => {
+                    let name = file_entry_name(item, class_type)?;
+                    path = join_path(&path, &name);
+                }
+                _ => return None,
+            }
+        }
+
+        Some(path)
+    }
+}
+
+/// Prefer the long name stored in the 0xBEEF0004 extension block - 优先使用扩展块中的长文件名
+fn file_entry_name(item: &[u8], class_type: u8) -> Option<String> {
+    let is_unicode = class_type & 0x04 != 0;
+    let primary = item.get(12..)?;
+    let (short_name, short_len) = if is_unicode {
+        let name = read_utf16_cstr(primary);
+        let len = (name.encode_utf16().count() + 1) * 2;
+        (name, len)
+    } else {
+        let name = read_ansi_cstr(primary);
+        let len = name.len() + 1;
+        (name, len + len % 2)
+    };
+
+    let long_name = item
+        .get(12 + short_len..)
+        .and_then(|extension| {
+            let size = read_u16(extension, 0).ok()? as usize;
+            let version = read_u16(extension, 2).ok()?;
+            let signature = read_u32(extension, 4).ok()?;
+            if signature != 0xBEEF_0004 || size > extension.len() {
+                return None;
+            }
+            let mut offset = 18;
+            if version >= 7 {
+                offset += 18;
+            }
+            if version >= 3 {
+                offset += 2;
+            }
+            if version >= 9 {
+                offset += 4;
+            }
+            if version >= 8 {
+                offset += 4;
+            }
+            extension.get(offset..size).map(read_utf16_cstr)
+        })
+        .filter(|n| !n.is_empty());
+
+    long_name.or(Some(short_name))
+}
+
+fn join_path(base: &str, name: &str) -> String {
+    match (base.is_empty(), name.is_empty()) {
+        (true, _) => name.to_owned(),
+        (_, true) => base.to_owned(),
+        _ if base.ends_with('\\') => format!("{base}{name}"),
+        _ => format!("{base}\\{name}"),
+    }
+}
+
+fn decode_string(raw: &[u8], is_unicode: bool) -> String {
+    if is_unicode {
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        ansi::decode(raw).unwrap_or_else(|| String::from_utf8_lossy(raw).into_owned())
+    }
+}
+
+fn encode_string(value: &str, is_unicode: bool) -> Vec<u8> {
+    if is_unicode {
+        value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    } else {
+        value.as_bytes().to_vec()
+    }
+}
+
+/// TargetAnsi (260 bytes) + TargetUnicode (520 bytes), both null-terminated
+fn environment_block_data(target: &str) -> Vec<u8> {
+    let mut ansi: Vec<u8> = target
+        .chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .take(259)
+        .collect();
+    ansi.resize(260, 0);
+
+    let mut unicode: Vec<u8> = target
+        .encode_utf16()
+        .take(259)
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    unicode.resize(520, 0);
+
+    ansi.extend(unicode);
+    ansi
+}
+
+/// LinkInfo of a local drive or a network share, `None` when the drive type is unknown
+fn target_link_info(target_path: &str) -> Option<Vec<u8>> {
+    if let Some(unc) = target_path.strip_prefix(r"\\") {
+        let mut parts = unc.splitn(3, '\\');
+        let server = parts
+            .next()
+            .filter(|s| !s.is_empty() && !matches!(*s, "?" | "."))?;
+        let share = parts.next().filter(|s| !s.is_empty())?;
+        let net_name = format!(r"\\{server}\{share}");
+        return Some(link_info(
+            network_link(&net_name),
+            None,
+            parts.next().unwrap_or_default(),
+        ));
+    }
+
+    let root = target_path
+        .get(..3)
+        .filter(|root| root.as_bytes()[1..] == *b":\\")?;
+    Some(link_info(
+        volume_id(drive_type(root)?),
+        Some(target_path),
+        "",
+    ))
+}
+
+/// LinkInfo with a VolumeID and LocalBasePath, or a CommonNetworkRelativeLink when `base_path` is `None`
+fn link_info(location: Vec<u8>, base_path: Option<&str>, path_suffix: &str) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 0x24;
+    let mut body = Vec::new();
+    let mut push = |part: Vec<u8>| {
+        let offset = HEADER_SIZE + body.len() as u32;
+        body.extend(part);
+        offset
+    };
+    let location_offset = push(location);
+    let base_path_offset = base_path.map(|p| push(ansi_cstr(p))).unwrap_or(0);
+    let suffix_offset = push(ansi_cstr(path_suffix));
+    let base_path_unicode_offset = base_path.map(|p| push(unicode_cstr(p))).unwrap_or(0);
+    let suffix_unicode_offset = push(unicode_cstr(path_suffix));
+
+    let (info_flags, volume_id_offset, network_offset) = match base_path {
+        Some(_) => (VOLUME_ID_AND_LOCAL_BASE_PATH, location_offset, 0),
+        None => (
+            COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX,
+            0,
+            location_offset,
+        ),
+    };
+    let size = HEADER_SIZE + body.len() as u32;
+
+    let mut data = Vec::with_capacity(size as usize);
+    for value in [
+        size,
+        HEADER_SIZE,
+        info_flags,
+        volume_id_offset,
+        base_path_offset,
+        network_offset,
+        suffix_offset,
+        base_path_unicode_offset,
+        suffix_unicode_offset,
+    ] {
+        data.extend(value.to_le_bytes());
+    }
+    data.extend(body);
+    data
+}
+
+/// VolumeID without serial number and label
+fn volume_id(drive_type: u32) -> Vec<u8> {
+    let mut data = [
+        0x11u32.to_le_bytes(),    // VolumeIDSize
+        drive_type.to_le_bytes(), // DriveType
+        0u32.to_le_bytes(),       // DriveSerialNumber
+        0x10u32.to_le_bytes(),    // VolumeLabelOffset
+    ]
+    .concat();
+    data.push(0); // VolumeLabel
+    data
+}
+
+/// CommonNetworkRelativeLink of a share like `\\server\share`, without device name
+fn network_link(net_name: &str) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 0x1C;
+    let ansi = ansi_cstr(net_name);
+    let unicode = unicode_cstr(net_name);
+    let net_name_unicode_offset = HEADER_SIZE + ansi.len() as u32;
+    let size = net_name_unicode_offset + unicode.len() as u32;
+
+    let mut data = Vec::with_capacity(size as usize);
+    for value in [
+        size,
+        0, // CommonNetworkRelativeLinkFlags
+        HEADER_SIZE,
+        0, // DeviceNameOffset
+        0, // NetworkProviderType
+        net_name_unicode_offset,
+        0, // DeviceNameOffsetUnicode
+    ] {
+        data.extend(value.to_le_bytes());
+    }
+    data.extend(ansi);
+    data.extend(unicode);
+    data
+}
+
+/// Null-terminated string of the ANSI code page, unmapped characters become '?'
+fn ansi_cstr(value: &str) -> Vec<u8> {
+    let mut data = ansi::encode(value).unwrap_or_else(|| {
+        value
+            .chars()
+            .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+            .collect()
+    });
+    data.push(0);
+    data
+}
+
+fn unicode_cstr(value: &str) -> Vec<u8> {
+    let mut data = encode_string(value, true);
+    data.extend([0, 0]);
+    data
+}
+
+/// `GetDriveTypeW` of a root like "C:\\", `None` when there is no such drive
+#[cfg(windows)]
+fn drive_type(root: &str) -> Option<u32> {
+    use windows::{Win32::Storage::FileSystem::GetDriveTypeW, core::HSTRING};
+
+    let drive_type = unsafe { GetDriveTypeW(&HSTRING::from(root)) };
+    // DRIVE_UNKNOWN, DRIVE_NO_ROOT_DIR
+    (drive_type > 1).then_some(drive_type)
+}
+
+/// The drive type can't be queried outside Windows
+#[cfg(not(windows))]
+fn drive_type(_root: &str) -> Option<u32> {
+    None
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| anyhow!("Unexpected end of data at {offset:#x}"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| anyhow!("Unexpected end of data at {offset:#x}"))
+}
+
+fn read_ansi_cstr(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    decode_string(&data[..end], false)
+}
+
+fn read_utf16_cstr(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Read a string whose offset is stored at `offset_pos` - 读取偏移量指向的字符串
+fn read_offset_ansi(data: &[u8], offset_pos: usize) -> Option<String> {
+    let offset = read_u32(data, offset_pos).ok()? as usize;
+    (offset != 0)
+        .then(|| data.get(offset..).map(read_ansi_cstr))
+        .flatten()
+}
+
+fn read_offset_utf16(data: &[u8], offset_pos: usize) -> Option<String> {
+    let offset = read_u32(data, offset_pos).ok()? as usize;
+    (offset != 0)
+        .then(|| data.get(offset..).map(read_utf16_cstr))
+        .flatten()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn peek(&self, len: usize) -> Result<&'a [u8]> {
+        self.data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| anyhow!("Unexpected end of data at {:#x}", self.pos))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self.peek(len)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let value = read_u16(self.data, self.pos)?;
+        self.pos += 2;
+        Ok(value)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos.min(self.data.len())..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn resolves_environment_target_and_icon() {
+        let shell_link = ShellLink::open(&fixture("notepad.lnk")).unwrap();
+
+        assert!(shell_link.is_unicode());
+        assert_eq!(
+            shell_link.target_path().as_deref(),
+            Some(r"%windir%\system32\notepad.exe")
+        );
+        assert_eq!(
+            shell_link.icon_location(),
+            Some((r"%windir%\system32\notepad.exe".to_owned(), 0))
+        );
+        assert_eq!(
+            shell_link.working_dir().as_deref(),
+            Some(r"%windir%\system32")
+        );
+        assert_eq!(shell_link.description(), None);
+    }
+
+    #[test]
+    fn resolves_link_info_target_and_strings() {
+        let shell_link = ShellLink::open(&fixture("far_cry_5.lnk")).unwrap();
+
+        assert_eq!(
+            shell_link.target_path().as_deref(),
+            Some(r"D:\Games\Far Cry 5\bin\FarCry5.exe")
+        );
+        assert_eq!(
+            shell_link.icon_location(),
+            Some((
+                r"C:\Users\Me\AppData\Local\LinkEcho\icons\far cry 5.ico".to_owned(),
+                0
+            ))
+        );
+        assert_eq!(
+            shell_link.arguments().as_deref(),
+            Some("-windowed -skipintro")
+        );
+        assert_eq!(
+            shell_link.description().as_deref(),
+            Some("Far Cry 5 – Ubisoft")
+        );
+        assert_eq!(shell_link.show_command(), 7);
+        assert_eq!(shell_link.hotkey(), 0x0646);
+    }
+
+    #[test]
+    fn resolves_id_list_target_with_long_names() {
+        let mut shell_link = ShellLink::open(&fixture("far_cry_5.lnk")).unwrap();
+        shell_link.link_info = None;
+
+        assert_eq!(
+            shell_link.target_path().as_deref(),
+            Some(r"D:\Games\Far Cry 5\bin\FarCry5.exe")
+        );
+    }
+
+    #[test]
+    fn resolves_network_target() {
+        let shell_link = ShellLink::open(&fixture("network_ansi.lnk")).unwrap();
+
+        assert!(!shell_link.is_unicode());
+        assert!(
+            shell_link
+                .target_path()
+                .is_some_and(|p| p.starts_with(r"\\fileserver\tools\") && p.ends_with(r"\app.exe"))
+        );
+        assert_eq!(shell_link.icon_index(), 2);
+    }
+
+    #[test]
+    fn shell_folder_has_no_target() {
+        let shell_link = ShellLink::open(&fixture("uwp_calculator.lnk")).unwrap();
+
+        assert_eq!(shell_link.target_path(), None);
+        assert_eq!(shell_link.icon_location(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let data = std::fs::read(fixture("far_cry_5.lnk")).unwrap();
+
+        assert!(ShellLink::parse(&data[..0x40]).is_err());
+        assert!(ShellLink::parse(&data[..0x60]).is_err());
+
+        let mut bad_clsid = data.clone();
+        bad_clsid[4] ^= 0xFF;
+        assert!(ShellLink::parse(&bad_clsid).is_err());
+    }
+
+    fn fixture_corpus() -> Vec<(PathBuf, Vec<u8>)> {
+        let mut corpus: Vec<_> = std::fs::read_dir(fixture(""))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "lnk"))
+            .map(|p| {
+                let data = std::fs::read(&p).unwrap();
+                (p, data)
+            })
+            .collect();
+        corpus.sort();
+        assert!(!corpus.is_empty());
+        corpus
+    }
+
+    #[test]
+    fn round_trip_is_byte_identical() {
+        for (path, data) in fixture_corpus() {
+            let shell_link = ShellLink::parse(&data).unwrap();
+            let written = shell_link.to_bytes().unwrap();
+
+            assert_eq!(written, data, "{path:?}");
+            assert_eq!(ShellLink::parse(&written).unwrap(), shell_link, "{path:?}");
+        }
+    }
+
+    #[test]
+    fn icon_edit_keeps_other_sections() {
+        for (path, data) in fixture_corpus() {
+            let original = ShellLink::parse(&data).unwrap();
+            let mut shell_link = original.clone();
+            shell_link
+                .set_icon_location(r"C:\Icons\app.ico", 3)
+                .unwrap();
+            let edited = ShellLink::parse(&shell_link.to_bytes().unwrap()).unwrap();
+
+            assert_eq!(
+                edited.icon_location(),
+                Some((r"C:\Icons\app.ico".to_owned(), 3)),
+                "{path:?}"
+            );
+            assert_eq!(edited.id_list, original.id_list, "{path:?}");
+            assert_eq!(edited.link_info, original.link_info, "{path:?}");
+            assert_eq!(edited.tail, original.tail, "{path:?}");
+            assert_eq!(edited.target_path(), original.target_path(), "{path:?}");
+            for field in [
+                StringField::Name,
+                StringField::RelativePath,
+                StringField::WorkingDir,
+                StringField::Arguments,
+            ] {
+                assert_eq!(edited.string(field), original.string(field), "{path:?}");
+            }
+            assert_eq!(
+                edited.extra_data,
+                original
+                    .extra_data
+                    .iter()
+                    .filter(|b| b.signature != ICON_ENVIRONMENT_BLOCK)
+                    .cloned()
+                    .collect::<Vec<_>>(),
+                "{path:?}"
+            );
+
+            // Restoring the icon location gives back the original icon
+            if original.is_unicode() {
+                let (icon_path, icon_index) = original.icon_location().unwrap_or_default();
+                shell_link
+                    .set_icon_location(&icon_path, icon_index)
+                    .unwrap();
+                assert_eq!(shell_link.icon_location(), original.icon_location());
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_link_is_converted_to_unicode() {
+        let mut shell_link = ShellLink::new(r"C:\Tools\app.exe");
+        shell_link.set_flag(IS_UNICODE, false);
+        shell_link
+            .set_string(StringField::Arguments, Some("--safe"))
+            .unwrap();
+        assert!(!shell_link.is_unicode());
+
+        shell_link
+            .set_string(StringField::Name, Some("工具"))
+            .unwrap();
+        let parsed = ShellLink::parse(&shell_link.to_bytes().unwrap()).unwrap();
+
+        assert!(parsed.is_unicode());
+        assert_eq!(parsed.description().as_deref(), Some("工具"));
+        assert_eq!(parsed.arguments().as_deref(), Some("--safe"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn unknown_code_page_is_not_converted() {
+        let data = std::fs::read(fixture("network_ansi.lnk")).unwrap();
+        let mut shell_link = ShellLink::parse(&data).unwrap();
+
+        assert!(
+            shell_link
+                .set_string(StringField::Arguments, Some("工具"))
+                .is_err()
+        );
+        assert_eq!(shell_link.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn long_string_is_rejected() {
+        let mut shell_link = ShellLink::new(r"C:\Tools\app.exe");
+        let long = "a".repeat(u16::MAX as usize + 1);
+
+        assert!(
+            shell_link
+                .set_string(StringField::Arguments, Some(&long))
+                .is_err()
+        );
+        assert_eq!(shell_link.arguments(), None);
+    }
+
+    #[test]
+    fn new_link_to_share_has_network_link_info() {
+        let target = r"\\fileserver\tools\Café\app.exe";
+        let shell_link = ShellLink::parse(&ShellLink::new(target).to_bytes().unwrap()).unwrap();
+
+        let link_info = shell_link.link_info.as_deref().unwrap();
+        assert_eq!(
+            read_u32(link_info, 8).unwrap(),
+            COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX
+        );
+        assert_eq!(shell_link.flags() & HAS_EXP_STRING, 0);
+        assert_eq!(shell_link.target_path().as_deref(), Some(target));
+    }
+
+    #[test]
+    fn local_link_info_has_the_drive_type() {
+        const DRIVE_REMOVABLE: u32 = 2;
+        let mut shell_link = ShellLink::new(r"\\fileserver\tools\app.exe");
+        shell_link.link_info = Some(link_info(
+            volume_id(DRIVE_REMOVABLE),
+            Some(r"E:\Portable\app.exe"),
+            "",
+        ));
+
+        let link_info = shell_link.link_info.as_deref().unwrap();
+        let volume_id_offset = read_u32(link_info, 0xC).unwrap() as usize;
+        assert_eq!(
+            read_u32(link_info, volume_id_offset + 4).unwrap(),
+            DRIVE_REMOVABLE
+        );
+        assert_eq!(
+            shell_link.target_path().as_deref(),
+            Some(r"E:\Portable\app.exe")
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn unknown_drive_type_omits_link_info() {
+        let shell_link = ShellLink::new(r"C:\Tools\app.exe");
+
+        assert!(shell_link.link_info.is_none());
+        assert_eq!(
+            shell_link.target_path().as_deref(),
+            Some(r"C:\Tools\app.exe")
+        );
+    }
+}
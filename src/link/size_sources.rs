@@ -0,0 +1,111 @@
+use super::{manifest::PackManifest, normalize::normalize};
+use crate::image::ico_profile::IcoProfile;
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use log::*;
+
+/// Formats a per-size image can have
+const SIZE_SOURCE_EXT: [&str; 5] = ["png", "svg", "bmp", "webp", "tiff"];
+
+/// Hand-made images of single frame sizes of `source`, used as they are instead of resampling it - 分尺寸图标
+///
+/// Files next to `source` named like it with a size suffix ("app-16.png", "app_32x32.png",
+/// "app-48px.png"), then the `sizes` of its pack manifest, which win on the same size.
+pub fn size_sources(source: &Path) -> Result<BTreeMap<u32, PathBuf>> {
+    let mut sources = sibling_sources(source)?;
+    sources.extend(manifest_sources(source));
+    Ok(sources)
+}
+
+/// Base name and size of a per-size image, "app-16.png" is ("app", 16)
+///
+/// The size must be one a profile writes, so "Windows-10.png" is not taken for a frame.
+pub fn size_suffix(path: &Path) -> Option<(&str, u32)> {
+    let ext = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+    if !SIZE_SOURCE_EXT.contains(&ext.as_str()) {
+        return None;
+    }
+
+    let stem = path.file_stem().and_then(OsStr::to_str)?;
+    let (base, suffix) = stem.rsplit_once(['-', '_'])?;
+    let size = match suffix.strip_suffix("px") {
+        Some(size) => size,
+        None => match suffix.split_once(['x', 'X']) {
+            Some((width, height)) if width == height => width,
+            Some(_) => return None,
+            None => suffix,
+        },
+    };
+    if base.is_empty() || size.is_empty() || !size.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let size = size.parse::<u32>().ok()?;
+    IcoProfile::is_frame_size(size).then_some((base, size))
+}
+
+/// Files of the folder of `source` that share its base name and carry a size suffix
+fn sibling_sources(source: &Path) -> Result<BTreeMap<u32, PathBuf>> {
+    let Some((dir, stem)) = source
+        .parent()
+        .zip(source.file_stem().and_then(OsStr::to_str))
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let base = size_suffix(source).map_or(stem, |(base, _)| base);
+
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    // 同一尺寸有多个文件时取路径最靠前的
+    files.sort();
+
+    let mut sources = BTreeMap::new();
+    for file in files {
+        let size = size_suffix(&file)
+            .filter(|(sibling_base, _)| sibling_base.eq_ignore_ascii_case(base))
+            .map(|(_, size)| size);
+        if let Some(size) = size {
+            sources.entry(size).or_insert(file);
+        }
+    }
+    Ok(sources)
+}
+
+/// `sizes` of the manifest icon whose default file is `source`, from the nearest pack manifest above it
+///
+/// Variants don't use them, a hand-made 16px image only fits the icon it was made for.
+fn manifest_sources(source: &Path) -> BTreeMap<u32, PathBuf> {
+    let stem = source
+        .file_stem()
+        .map(|stem| normalize(&stem.to_string_lossy()))
+        .unwrap_or_default();
+
+    for pack in source.ancestors().skip(1) {
+        let manifest = match PackManifest::load(pack) {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("{e}");
+                return BTreeMap::new();
+            }
+        };
+
+        return manifest
+            .icons
+            .iter()
+            .find(|(id, icon)| match &icon.file {
+                Some(file) => pack.join(file) == source,
+                None => normalize(id) == stem,
+            })
+            .map(|(_, icon)| icon.size_sources(pack))
+            .unwrap_or_default();
+    }
+    BTreeMap::new()
+}
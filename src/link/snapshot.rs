@@ -0,0 +1,387 @@
+use super::{
+    backend::{ShortcutBackend, backends},
+    conversion_cache::ICONS_DIR,
+    info::ManageLinkProp,
+    journal::Batch,
+    list::ShortcutSource,
+};
+use crate::utils::ensure_local_app_folder_exists;
+
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsStr,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use log::*;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_FILE: &str = "snapshot.json";
+/// Only image files are copied, programs and libraries are expected on the new machine
+const COPY_ICON_EXT: [&str; 7] = ["ico", "png", "svg", "bmp", "webp", "tiff", "xpm"];
+
+/// Icon assignments of every shortcut, saved as `snapshot.json` in the archive - 图标快照
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created_at: String,
+    /// User profile of the exporting machine, replaced by the local one on import
+    pub user_profile: String,
+    pub shortcuts: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub link_path: String,
+    /// Unexpanded icon location, as written in the shortcut
+    pub icon_path: String,
+    pub icon_index: i32,
+    /// Copy of the icon inside the archive
+    #[serde(default)]
+    pub icon_file: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub applied: usize,
+    pub skipped: usize,
+    /// Icons that are neither in the archive nor on this computer
+    pub missing: usize,
+}
+
+fn user_profile() -> String {
+    env::var("USERPROFILE")
+        .or_else(|_| env::var("HOME"))
+        .unwrap_or_default()
+}
+
+/// Replace the `from` profile prefix of a path with `to`, case-insensitively
+///
+/// The prefix must end at a separator, `C:\Users\Al` is not the profile of `C:\Users\Alice`.
+fn remap_profile(path: &str, from: &str, to: &str) -> String {
+    let (Some(prefix), Some(rest)) = (path.get(..from.len()), path.get(from.len()..)) else {
+        return path.to_owned();
+    };
+    match !from.is_empty()
+        && prefix.eq_ignore_ascii_case(from)
+        && (rest.is_empty() || rest.starts_with(['\\', '/']))
+    {
+        true => format!("{to}{rest}"),
+        false => path.to_owned(),
+    }
+}
+
+/// Every shortcut below the sources' folders with the backend that reads it
+///
+/// A shortcut in the folders of several sources is listed once.
+pub fn discover_shortcuts(
+    sources: &[ShortcutSource],
+) -> Vec<(PathBuf, &'static dyn ShortcutBackend)> {
+    let mut seen = HashSet::new();
+    sources
+        .iter()
+        .filter_map(|source| {
+            source
+                .dirs()
+                .inspect_err(|e| error!("Failed to get {} path: {e}", source.name()))
+                .ok()
+        })
+        .flatten()
+        .flat_map(|dir| {
+            backends()
+                .iter()
+                .flat_map(move |backend| backend.discover(&dir).into_iter().map(|p| (p, *backend)))
+                .collect::<Vec<_>>()
+        })
+        .filter(|(path, _)| seen.insert(path.clone()))
+        .collect()
+}
+
+/// Pack the icon location of every shortcut of `sources` and the icon images into a zip archive
+pub fn export_snapshot(archive_path: &Path, sources: &[ShortcutSource]) -> Result<usize> {
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // 同一图标只打包一次
+    let mut icon_files: HashMap<PathBuf, String> = HashMap::new();
+    let mut shortcuts = Vec::new();
+
+    for (link_path, backend) in discover_shortcuts(sources) {
+        let Some((icon_path, icon_index)) = backend
+            .icon_location(&link_path)
+            .inspect_err(|e| error!("Failed to get the icon location:\n{link_path:?}\n{e}"))
+            .ok()
+            .flatten()
+        else {
+            continue;
+        };
+
+        let icon_source = PathBuf::from(ManageLinkProp::convert_env_to_path(&icon_path));
+        let should_copy = icon_source.is_file()
+            && icon_source
+                .extension()
+                .and_then(OsStr::to_str)
+                .is_some_and(|ext| COPY_ICON_EXT.contains(&ext.to_lowercase().as_str()));
+
+        let icon_file = match icon_files.get(&icon_source) {
+            Some(name) => Some(name.clone()),
+            None if should_copy => {
+                let file_name = icon_source
+                    .file_name()
+                    .and_then(OsStr::to_str)
+                    .with_context(|| format!("Failed to get icon name: {icon_source:?}"))?;
+                let name = format!("icons/{}/{file_name}", icon_files.len());
+                zip.start_file(name.as_str(), options)?;
+                zip.write_all(&std::fs::read(&icon_source)?)?;
+                icon_files.insert(icon_source, name.clone());
+                Some(name)
+            }
+            None => None,
+        };
+
+        shortcuts.push(SnapshotEntry {
+            link_path: link_path.to_string_lossy().into_owned(),
+            icon_path,
+            icon_index,
+            icon_file,
+        });
+    }
+
+    let count = shortcuts.len();
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        user_profile: user_profile(),
+        shortcuts,
+    };
+    zip.start_file(SNAPSHOT_FILE, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&snapshot)?)?;
+    zip.finish()?;
+
+    info!(
+        "{}: {count}\n{archive_path:?}",
+        t!("SUCCESS_EXPORT_SNAPSHOT")
+    );
+
+    Ok(count)
+}
+
+/// Extract an icon of the archive into the converted icons folder, an identical file is reused
+fn extract_icon(zip: &mut ZipArchive<File>, name: &str, icons_dir: &Path) -> Result<PathBuf> {
+    let mut data = Vec::new();
+    zip.by_name(name)?.read_to_end(&mut data)?;
+
+    let file_name = Path::new(name)
+        .file_name()
+        .with_context(|| format!("Invalid icon in the snapshot: {name}"))?;
+    let stem = Path::new(file_name)
+        .file_stem()
+        .unwrap_or(file_name)
+        .to_string_lossy();
+    let ext = Path::new(file_name).extension().map(OsStr::to_string_lossy);
+
+    for n in 1..=u16::MAX {
+        let icon_path = match (n, &ext) {
+            (1, _) => icons_dir.join(file_name),
+            (_, Some(ext)) => icons_dir.join(format!("{stem} ({n}).{ext}")),
+            (_, None) => icons_dir.join(format!("{stem} ({n})")),
+        };
+        if !icon_path.exists() {
+            std::fs::write(&icon_path, &data)?;
+            return Ok(icon_path);
+        }
+        if std::fs::read(&icon_path)? == data {
+            return Ok(icon_path);
+        }
+    }
+    Err(anyhow!("No free file name for the icon: {name}"))
+}
+
+/// Reapply a snapshot, paths under the exporting user's profile are moved to the local one
+///
+/// Shortcuts that are not found at their remapped path are looked up by file name in `sources`.
+/// The changes are one journal batch, so the whole import can be undone.
+pub fn import_snapshot(archive_path: &Path, sources: &[ShortcutSource]) -> Result<ImportSummary> {
+    import_snapshot_into(archive_path, sources, ensure_local_app_folder_exists()?)
+}
+
+/// Import with `app_dir` as the folder of the journal and the extracted icons
+fn import_snapshot_into(
+    archive_path: &Path,
+    sources: &[ShortcutSource],
+    app_dir: PathBuf,
+) -> Result<ImportSummary> {
+    let mut zip = ZipArchive::new(File::open(archive_path)?)?;
+    let snapshot: Snapshot = serde_json::from_reader(zip.by_name(SNAPSHOT_FILE)?)
+        .with_context(|| format!("Failed to parse {SNAPSHOT_FILE}"))?;
+    ensure!(
+        snapshot.version <= SNAPSHOT_VERSION,
+        "Unsupported snapshot version: {}",
+        snapshot.version
+    );
+
+    let icons_dir = app_dir.join(ICONS_DIR);
+    std::fs::create_dir_all(&icons_dir)?;
+
+    let local_profile = user_profile();
+    let local_links: HashMap<String, PathBuf> = discover_shortcuts(sources)
+        .into_iter()
+        .filter_map(|(path, _)| Some((path.file_name()?.to_string_lossy().to_lowercase(), path)))
+        .collect();
+
+    let mut extracted: HashMap<String, PathBuf> = HashMap::new();
+    let mut batch = Batch::new(app_dir, backends());
+    let mut summary = ImportSummary::default();
+
+    for entry in snapshot.shortcuts {
+        let remapped = PathBuf::from(remap_profile(
+            &entry.link_path,
+            &snapshot.user_profile,
+            &local_profile,
+        ));
+        let link_path = match remapped.is_file() {
+            true => remapped,
+            false => match remapped
+                .file_name()
+                .and_then(|n| local_links.get(&n.to_string_lossy().to_lowercase()))
+            {
+                Some(path) => path.clone(),
+                None => {
+                    warn!("{}: {}", t!("SNAPSHOT_SHORTCUT_NOT_FOUND"), entry.link_path);
+                    summary.skipped += 1;
+                    continue;
+                }
+            },
+        };
+
+        let icon_path = match &entry.icon_file {
+            Some(name) => match extracted.get(name) {
+                Some(path) => path.clone(),
+                None => {
+                    let path = extract_icon(&mut zip, name, &icons_dir)?;
+                    extracted.insert(name.clone(), path.clone());
+                    path
+                }
+            }
+            .to_string_lossy()
+            .into_owned(),
+            None => remap_profile(&entry.icon_path, &snapshot.user_profile, &local_profile),
+        };
+
+        // 新电脑上没有的程序或图标不写入，否则快捷方式会显示空白图标
+        if !Path::new(&ManageLinkProp::convert_env_to_path(&icon_path)).exists() {
+            warn!(
+                "{}: {link_path:?}\n{icon_path}",
+                t!("SNAPSHOT_ICON_NOT_FOUND")
+            );
+            summary.missing += 1;
+            continue;
+        }
+
+        match batch.set_icon(&link_path, &icon_path, entry.icon_index) {
+            Ok(()) => {
+                info!("{}:\n{link_path:?}\n{icon_path}", t!("SHORTCUT"));
+                summary.applied += 1;
+            }
+            Err(e) => {
+                error!("Failed to set icon:\n{link_path:?}\n{icon_path}\n{e}");
+                summary.skipped += 1;
+            }
+        }
+    }
+
+    batch.finish()?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::internet_shortcut::InternetShortcut;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("linkecho-snapshot-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn icon_location(link_path: &Path) -> Option<(String, i32)> {
+        InternetShortcut::open(link_path).unwrap().icon_location()
+    }
+
+    #[test]
+    fn profile_is_remapped_at_a_separator() {
+        let remap = |path| remap_profile(path, r"C:\Users\Al", r"D:\Users\Bob");
+        assert_eq!(
+            remap(r"c:\users\al\Desktop\App.lnk"),
+            r"D:\Users\Bob\Desktop\App.lnk"
+        );
+        assert_eq!(remap(r"C:\Users\Al/Desktop"), r"D:\Users\Bob/Desktop");
+        assert_eq!(remap(r"C:\Users\Al"), r"D:\Users\Bob");
+        assert_eq!(
+            remap(r"C:\Users\Alice\Desktop\App.lnk"),
+            r"C:\Users\Alice\Desktop\App.lnk"
+        );
+        assert_eq!(remap(r"C:\Users"), r"C:\Users");
+        assert_eq!(
+            remap_profile("/home/al/x", "/home/al", "/home/bob"),
+            "/home/bob/x"
+        );
+        assert_eq!(remap_profile("/home/al/x", "", "/home/bob"), "/home/al/x");
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let dir = temp_dir("round_trip");
+        let links_dir = dir.join("links");
+        let app_dir = dir.join("app");
+        std::fs::create_dir_all(&links_dir).unwrap();
+        std::fs::create_dir_all(app_dir.join(ICONS_DIR)).unwrap();
+
+        let icon_source = dir.join("app.ico");
+        std::fs::write(&icon_source, b"icon").unwrap();
+        let link_path = links_dir.join("App.url");
+        let mut shortcut = InternetShortcut::new("https://example.com/");
+        shortcut.set_icon_location(&icon_source.to_string_lossy(), 0);
+        shortcut.save(&link_path).unwrap();
+        // 无图标的快捷方式不导出
+        InternetShortcut::new("https://example.org/")
+            .save(&links_dir.join("Other.url"))
+            .unwrap();
+
+        let sources = [ShortcutSource::Other(links_dir)];
+        let archive_path = dir.join("snapshot.zip");
+        assert_eq!(export_snapshot(&archive_path, &sources).unwrap(), 1);
+
+        let mut shortcut = InternetShortcut::open(&link_path).unwrap();
+        shortcut.set_icon_location("", 0);
+        shortcut.save(&link_path).unwrap();
+        // 同名的其他图标不被覆盖
+        std::fs::write(app_dir.join(ICONS_DIR).join("app.ico"), b"other").unwrap();
+
+        for _ in 0..2 {
+            let summary = import_snapshot_into(&archive_path, &sources, app_dir.clone()).unwrap();
+            assert_eq!(
+                (summary.applied, summary.skipped, summary.missing),
+                (1, 0, 0)
+            );
+
+            let icon_path = app_dir.join(ICONS_DIR).join("app (2).ico");
+            assert_eq!(
+                icon_location(&link_path),
+                Some((icon_path.to_string_lossy().into_owned(), 0))
+            );
+            assert_eq!(std::fs::read(&icon_path).unwrap(), b"icon");
+        }
+        // 相同内容的图标再次导入时复用
+        assert!(!app_dir.join(ICONS_DIR).join("app (3).ico").exists());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{image::icongen::image_to_ico, utils::ensure_local_app_folder_exists};
+use super::{
+    backend::ShortcutBackend, conversion_cache::ConversionCache, journal::Journal,
+    size_sources::size_sources,
+};
+use crate::image::{ico_profile::IcoProfile, icongen::image_to_ico, pe_icon::PE_ICON_EXT};
 
 use std::{
     ffi::OsStr,
@@ -8,54 +12,70 @@ use std::{
 use anyhow::{Context, Result, anyhow};
 use log::*;
 use rust_i18n::t;
-use winsafe::{IPersistFile, co, prelude::*};
 
-pub fn initialize_com_and_create_shell_link() -> Result<(winsafe::IShellLink, IPersistFile)> {
-    let _com_lib =
-        winsafe::CoInitializeEx(co::COINIT::APARTMENTTHREADED | co::COINIT::DISABLE_OLE1DDE)
-            .context("Failed to initialize com library")?;
+/// Change the icon as a batch of its own, recorded in the journal
+pub fn set_link_icon_location(
+    backends: &[&dyn ShortcutBackend],
+    link_path: impl AsRef<Path>,
+    icon_path: &str,
+    icon_index: i32,
+) -> Result<()> {
+    let mut batch = Journal::begin(backends)?;
+    batch.set_icon(link_path.as_ref(), icon_path, icon_index)?;
+    batch.finish()
+}
+
+/// Where `process_icon` puts the icon, without converting it
+pub fn converted_icon_path(icon_path: &Path) -> Result<PathBuf> {
+    match is_native_icon(icon_path)? {
+        true => Ok(icon_path.to_path_buf()),
+        false => ConversionCache::load()?.output_path(
+            icon_path,
+            &size_sources(icon_path)?,
+            IcoProfile::load()?,
+        ),
+    }
+}
 
-    let shell_link = winsafe::CoCreateInstance::<winsafe::IShellLink>(
-        &co::CLSID::ShellLink,
-        None,
-        co::CLSCTX::INPROC_SERVER,
-    )
-    .context("Failed to create an IUnknown-derived COM object - ShellLink")?;
+/// .ico and the icons of programs and libraries are used as they are, other images are converted once per content
+///
+/// Per-size images next to the source or in its pack manifest become the frames of their size.
+pub fn process_icon(icon_path: &Path) -> Result<PathBuf> {
+    if is_native_icon(icon_path)? {
+        return Ok(icon_path.to_path_buf());
+    }
 
-    let persist_file: IPersistFile = shell_link
-        .QueryInterface()
-        .context("Failed to query for ShellLink's IPersistFile interface")?;
+    let profile = IcoProfile::load()?;
+    let size_sources = size_sources(icon_path)?;
+    let mut cache = ConversionCache::load()?;
+    let convert_icon_path = cache.output_path(icon_path, &size_sources, profile)?;
+    if !convert_icon_path.is_file() {
+        if let Some(icon_data_path) = convert_icon_path.parent() {
+            std::fs::create_dir_all(icon_data_path)?;
+        }
+        let icon_name = icon_path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default();
+        image_to_ico(
+            icon_path,
+            &size_sources,
+            &convert_icon_path,
+            icon_name,
+            profile,
+        )?;
+        info!("{}: {}", t!("SUCCESS_IMG_TO_ICO"), icon_path.display());
+    };
+    cache.save()?;
 
-    Ok((shell_link, persist_file))
+    Ok(convert_icon_path)
 }
 
-pub fn process_icon(icon_path: &Path) -> Result<PathBuf> {
+fn is_native_icon(icon_path: &Path) -> Result<bool> {
     let ext = icon_path
         .extension()
         .and_then(OsStr::to_str)
         .with_context(|| anyhow!("Not an icon: {icon_path:?}"))?;
-
-    let icon_path = match ext {
-        "ico" | "exe" => icon_path.to_path_buf(),
-        _ => {
-            // 配置文件
-            // 1.保存到软件转换图标目录（默认）
-            // 2.保存到图标目录```if let Some(convert_icon_path) = icon_path.with_extension("ico")```
-            let app_data_path = ensure_local_app_folder_exists()?;
-            let icon_data_path = app_data_path.join("icons");
-            std::fs::create_dir_all(&icon_data_path)?;
-            let icon_name = icon_path
-                .file_stem()
-                .and_then(OsStr::to_str)
-                .with_context(|| anyhow!("Failed to get icon name: {icon_path:?}"))?;
-            let convert_icon_path = icon_data_path.join(format!("{icon_name}.ico"));
-            if !convert_icon_path.is_file() {
-                image_to_ico(icon_path, &convert_icon_path, icon_name)?;
-                info!("{}: {icon_name}.{ext}", t!("SUCCESS_IMG_TO_ICO"));
-            };
-            convert_icon_path
-        }
-    };
-
-    Ok(icon_path)
+    let ext = ext.to_lowercase();
+    Ok(ext == "ico" || PE_ICON_EXT.contains(&ext.as_str()))
 }
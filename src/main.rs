@@ -1,5 +1,4 @@
 #![allow(non_snake_case)]
-#![cfg(target_os = "windows")]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod components;
@@ -12,8 +11,14 @@ mod scripts;
 mod utils;
 
 use crate::{
-    components::{msgbox::Msgbox, tools::CustomizeIcon},
-    link::list::LinkList,
+    components::{icon_picker::IconPicker, msgbox::Msgbox, tools::CustomizeIcon},
+    link::{
+        icon_gc::{OrphanIcon, gc_sources},
+        list::{LinkList, ShortcutSource},
+        packs::IconPacks,
+        plan::MatchPlan,
+        snapshot::{export_snapshot, import_snapshot},
+    },
     utils::ensure_local_app_folder_exists,
 };
 
@@ -54,6 +59,10 @@ fn app() -> Element {
     let filter_name: Signal<Option<String>> = use_signal(|| None);
     let show_msgbox: Signal<Option<Msgbox>> = use_signal(|| None);
     let show_prop = use_signal(|| false);
+    let show_create = use_signal(|| false);
+    let show_plan: Signal<Option<MatchPlan>> = use_signal(|| None);
+    let show_orphans: Signal<Option<Vec<OrphanIcon>>> = use_signal(|| None);
+    let show_icon_picker: Signal<Option<IconPicker>> = use_signal(|| None);
     let read_tab = *current_tab.read();
     let customize_icon = use_signal(CustomizeIcon::default);
 
@@ -69,6 +78,7 @@ fn app() -> Element {
                 filter_name,
                 current_tab,
                 show_msgbox,
+                show_plan,
             }
             div {
                 display: "flex",
@@ -84,6 +94,7 @@ fn app() -> Element {
                         customize_icon,
                         show_msgbox,
                         show_prop,
+                        show_icon_picker,
                     }
                 } else if read_tab == Tab::Tools {
                     components::tools::tools {
@@ -91,6 +102,9 @@ fn app() -> Element {
                         current_tab,
                         customize_icon,
                         show_msgbox,
+                        show_create,
+                        show_orphans,
+                        show_icon_picker,
                     }
                 } else if read_tab == Tab::Log {
                     components::log::log {}
@@ -103,6 +117,15 @@ fn app() -> Element {
             components::status::status { link_list }
             components::msgbox::msgbox { show_msgbox, link_list, current_tab }
             components::properties::properties { show_prop, link_list }
+            components::create::create {
+                show_create,
+                link_list,
+                current_tab,
+                customize_icon,
+            }
+            components::plan::plan { show_plan, link_list, current_tab }
+            components::orphans::orphans { show_orphans, link_list }
+            components::icon_picker::icon_picker { show_icon_picker, link_list, customize_icon }
         }
     }
 }
@@ -129,29 +152,108 @@ fn setup_logger() -> Result<()> {
 
 fn handle_cli(args: Vec<String>) -> Result<bool> {
     match args[1].as_str() {
+        // -c <快捷方式> <图标> [图标索引]，索引仅用于exe/dll，负数为资源ID
         "-c" => {
             let link_path = args.get(2).unwrap();
             let icon_path = args.get(3).unwrap();
+            let icon_index = match args.get(4).map(|index| index.parse::<i32>()) {
+                Some(Ok(index)) => index,
+                Some(Err(_)) => std::process::exit(1),
+                None => 0,
+            };
             let link_path = Path::new(&link_path);
             let icon_path = Path::new(&icon_path);
-            cli::change_single_shortcut_icon(link_path, icon_path)
+            cli::change_single_shortcut_icon(link_path, icon_path, icon_index)
         }
+        // -C [快捷方式目录] <图标目录> [--pack <图标目录>]... [--variant <变体>] [--pinyin] [--dry-run | --plan <plan.json>]
         "-C" => {
-            let link_folder_path = args.get(2);
-            let icon_folder_path = args.get(3);
+            let mut mode = cli::PlanMode::Apply;
+            let mut folders = Vec::new();
+            // 优先级较低的图标包
+            let mut packs = IconPacks::default();
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--dry-run" => mode = cli::PlanMode::DryRun,
+                    "--plan" => match rest.next() {
+                        Some(plan_path) => mode = cli::PlanMode::Save(PathBuf::from(plan_path)),
+                        None => std::process::exit(1),
+                    },
+                    "--pack" => match rest.next() {
+                        Some(pack) => packs.add(PathBuf::from(pack)),
+                        None => std::process::exit(1),
+                    },
+                    "--variant" => match rest.next() {
+                        Some(variant) => packs.variant = Some(variant.to_owned()),
+                        None => std::process::exit(1),
+                    },
+                    "--pinyin" => packs.pinyin = true,
+                    _ => folders.push(arg),
+                }
+            }
 
-            match (link_folder_path, icon_folder_path) {
+            let (link_folder_path, icon_folder_path) = match folders.as_slice() {
                 // 如无第二个参数，则默认为桌面
-                (Some(icon_folder_path), None) => {
-                    let icon_folder_path = Path::new(&icon_folder_path);
-                    cli::change_all_shortcuts_icons(None, icon_folder_path)
-                }
-                (Some(link_folder_path), Some(icon_folder_path)) => {
-                    let link_folder_path = PathBuf::from(link_folder_path);
-                    let icon_folder_path = Path::new(&icon_folder_path);
-                    cli::change_all_shortcuts_icons(Some(link_folder_path), icon_folder_path)
+                [icon_folder_path] => (None, icon_folder_path),
+                [link_folder_path, icon_folder_path] => {
+                    (Some(PathBuf::from(link_folder_path)), icon_folder_path)
                 }
                 _ => std::process::exit(1),
+            };
+            packs.dirs.insert(0, PathBuf::from(icon_folder_path));
+            cli::change_all_shortcuts_icons(link_folder_path, &packs, mode)
+        }
+        // 应用保存的匹配计划中已接受的行
+        "-A" => {
+            let plan_path = Path::new(args.get(2).unwrap());
+            cli::apply_match_plan(plan_path)
+        }
+        // -g [快捷方式目录]... [--delete | --archive <icons.zip>]，仅列出时不做任何修改
+        "-g" => {
+            let mut mode = cli::GcMode::List;
+            let mut sources = gc_sources(&ShortcutSource::Desktop);
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--delete" => mode = cli::GcMode::Delete,
+                    "--archive" => match rest.next() {
+                        Some(archive_path) => {
+                            mode = cli::GcMode::Archive(PathBuf::from(archive_path))
+                        }
+                        None => std::process::exit(1),
+                    },
+                    _ => sources.push(ShortcutSource::Other(PathBuf::from(arg))),
+                }
+            }
+            cli::clean_orphan_icons(&sources, mode)
+        }
+        // -x <exe/dll> [图标索引] [输出.ico]，无索引时列出所有图标组
+        "-x" => {
+            let pe_path = Path::new(args.get(2).unwrap());
+            let icon_index = match args.get(3).map(|index| index.parse::<i32>()) {
+                Some(Ok(index)) => Some(index),
+                Some(Err(_)) => std::process::exit(1),
+                None => None,
+            };
+            let output_path = args.get(4).map(PathBuf::from);
+            cli::extract_pe_icon(pe_path, icon_index, output_path)
+        }
+        // -f <图标.ico>，列出所有帧
+        "-f" => cli::list_ico_frames(Path::new(args.get(2).unwrap())),
+        // -p [modern|compatible|high-dpi]，无参数时列出所有配置
+        "-p" => cli::set_ico_profile(args.get(2).map(String::as_str)),
+        // 导出/导入图标快照，可追加自定义的快捷方式目录
+        "-e" | "-i" => {
+            let archive_path = Path::new(args.get(2).unwrap());
+            let mut sources = vec![
+                ShortcutSource::Desktop,
+                ShortcutSource::StartMenu,
+                ShortcutSource::Applications,
+            ];
+            sources.extend(args[3..].iter().map(|p| ShortcutSource::Other(PathBuf::from(p))));
+            match args[1].as_str() {
+                "-e" => export_snapshot(archive_path, &sources).map(|_| true),
+                _ => import_snapshot(archive_path, &sources).map(|summary| summary.applied > 0),
             }
         }
         _ => std::process::exit(1),
@@ -12,7 +12,10 @@ use log::*;
 use rust_i18n::t;
 
 pub fn clear_icon_cache() {
-    let local_app_data = env::var("LOCALAPPDATA").expect("Failed to get the local app data path");
+    // Only Windows keeps an icon cache of Explorer - 仅 Windows 资源管理器有图标缓存
+    let Ok(local_app_data) = env::var("LOCALAPPDATA") else {
+        return notify(&t!("ERROR_ITERTATOR_EXPLORER"));
+    };
     let explorer_path = Path::new(&local_app_data).join("Microsoft\\Windows\\Explorer");
 
     if !explorer_path.is_dir() {
@@ -56,6 +59,12 @@ fn should_delete_file(path: &Path) -> bool {
     })
 }
 
+#[cfg(not(windows))]
+fn restart_explorer() -> Result<()> {
+    anyhow::bail!("Explorer only exists on Windows")
+}
+
+#[cfg(windows)]
 fn restart_explorer() -> Result<()> {
     // use std::time::Duration;
     use restart_explorer::{
@@ -1,111 +1,190 @@
-use crate::link::{
-    info::ManageLinkProp,
-    list::LinkList,
-    modify::partial_match_icon,
-    utils::{initialize_com_and_create_shell_link, process_icon},
+use crate::{
+    image::{
+        ico::IcoFile,
+        ico_profile::IcoProfile,
+        pe_icon::{PE_ICON_EXT, is_pe_icon_file, pe_icon, pe_icon_groups},
+    },
+    link::{
+        backend::{backend_for, backends},
+        icon_gc::{find_orphan_icons, remove_orphan_icons},
+        list::{LinkList, ShortcutSource},
+        packs::IconPacks,
+        plan::MatchPlan,
+        utils::{process_icon, set_link_icon_location},
+    },
 };
 
 use std::{
-    collections::HashMap,
     ffi::OsStr,
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result, anyhow};
-use glob::glob;
+use anyhow::{Context, Result, anyhow, ensure};
 use log::*;
 use rust_i18n::t;
-use winsafe::{co, prelude::*};
-
-pub fn change_all_shortcuts_icons(links_path: Option<PathBuf>, icons_path: &Path) -> Result<bool> {
-    let select_icons_folder_path = icons_path
-        .join("**\\*.*")
-        .to_str()
-        .map(str::to_owned)
-        .ok_or(anyhow!("Failed to get the path"))?;
-
-    let match_icon_ext = ["ico", "png", "svg", "bmp", "webp", "tiff", "exe"];
-    let mut icon_map = glob(&select_icons_folder_path)
-        .map_err(|e| anyhow!("Glob failed for {select_icons_folder_path}: {e}"))?
-        .filter_map(Result::ok)
-        .fold(HashMap::new(), |mut icon_map, file_path| {
-            if let Some((name, ext)) = file_path
-                .file_stem()
-                .and_then(OsStr::to_str)
-                .zip(file_path.extension().and_then(OsStr::to_str))
-                .map(|(stem, ext)| (stem.trim().to_lowercase(), ext.to_lowercase()))
-                .filter(|(_, ext)| match_icon_ext.contains(&ext.as_str()))
-            {
-                icon_map
-                    .entry(name)
-                    .and_modify(|existing| {
-                        if ext == "ico" {
-                            *existing = file_path.clone();
-                        }
-                    })
-                    .or_insert(file_path);
-            }
-
-            icon_map
-        });
-
-    let (shell_link, persist_file) = initialize_com_and_create_shell_link()?;
-    let link_list = links_path.map(LinkList::other).unwrap_or_default();
-    for link_prop in link_list.items.iter() {
-        let link_name = link_prop.name.trim().to_lowercase();
-        let link_path = &link_prop.path;
-        let link_target_path = Path::new(&link_prop.target_path);
-        let link_icon_path = Path::new(&link_prop.icon_path);
-
-        let matched_icon = icon_map
-            .remove(&link_name)
-            .or(partial_match_icon(&icon_map, &link_name))
-            .and_then(|p| process_icon(&p).ok());
-
-        let icon_path = match matched_icon {
-            Some(p) => {
-                if p == link_target_path || p == link_icon_path {
-                    continue;
-                }
-                p.to_string_lossy().into_owned()
-            }
-            None => continue,
-        };
 
-        if let Err(e) = persist_file.Load(link_path, co::STGM::WRITE) {
-            error!("Failed to load the shortcut:\n{link_path}\n{e}");
-            continue;
-        }
+/// What `change_all_shortcuts_icons` does with the computed match plan
+pub enum PlanMode {
+    Apply,
+    /// Only print the plan
+    DryRun,
+    /// Save the plan to a json file, it can be edited and applied with `apply_match_plan`
+    Save(PathBuf),
+}
 
-        if let Err(e) = shell_link.SetIconLocation(&icon_path, 0) {
-            error!("Failed to set icon:\n{link_path}\n{icon_path}\n{e}");
-            continue;
+pub fn change_all_shortcuts_icons(
+    links_path: Option<PathBuf>,
+    packs: &IconPacks,
+    mode: PlanMode,
+) -> Result<bool> {
+    let link_list = links_path.map(LinkList::other).unwrap_or_default();
+    let plan = MatchPlan::build(packs, &link_list.source, &link_list.items)?;
+    println!("{plan}");
+
+    match mode {
+        PlanMode::Apply => Ok(!plan.apply(backends())?.is_empty()),
+        PlanMode::DryRun => Ok(false),
+        PlanMode::Save(plan_path) => {
+            plan.save(&plan_path)?;
+            info!("{}: {plan_path:?}", t!("SUCCESS_SAVE_PLAN"));
+            Ok(false)
         }
+    }
+}
 
-        // Save a copy of the object to the specified file - 将对象的副本保存到指定文件
-        match persist_file.Save(None, true) {
-            Ok(()) => info!("{}:\n{link_path}\n{icon_path}", t!("SHORTCUT")),
-            Err(e) => {
-                error!("Failed to save the shortcut:\n{link_path}\n{e}");
-                continue;
-            }
-        }
+/// Apply the accepted rows of a saved match plan
+pub fn apply_match_plan(plan_path: &Path) -> Result<bool> {
+    let plan = MatchPlan::load(plan_path)?;
+    Ok(!plan.apply(backends())?.is_empty())
+}
+
+/// What `clean_orphan_icons` does with the unused icons it lists
+pub enum GcMode {
+    List,
+    Delete,
+    /// Copy them into a zip archive before deleting them
+    Archive(PathBuf),
+}
+
+/// Print the icons of the icons folder that nothing refers to, then delete or archive them
+pub fn clean_orphan_icons(sources: &[ShortcutSource], mode: GcMode) -> Result<bool> {
+    let orphans = find_orphan_icons(sources)?;
+    for orphan in &orphans {
+        println!(
+            "{:>10.2} KB  {}",
+            orphan.size as f64 / 1024.0,
+            orphan.path.display()
+        );
     }
+    let total = orphans.iter().map(|orphan| orphan.size).sum::<u64>();
+    println!(
+        "{}: {} ({:.2} KB)",
+        t!("ORPHAN_ICONS"),
+        orphans.len(),
+        total as f64 / 1024.0
+    );
+
+    let archive_path = match mode {
+        GcMode::List => return Ok(false),
+        GcMode::Delete => None,
+        GcMode::Archive(archive_path) => Some(archive_path),
+    };
+    let summary = remove_orphan_icons(sources, &orphans, archive_path.as_deref())?;
+    Ok(summary.removed > 0)
+}
+
+/// Print the icon groups of a program or library, or save the group of `icon_index` as an .ico
+///
+/// The output defaults to `{stem}-{icon_index}.ico` in the current folder.
+pub fn extract_pe_icon(
+    pe_path: &Path,
+    icon_index: Option<i32>,
+    output_path: Option<PathBuf>,
+) -> Result<bool> {
+    let Some(icon_index) = icon_index else {
+        for group in pe_icon_groups(pe_path)? {
+            let sizes = group
+                .sizes
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!("{:>4}  {:<8}  {sizes}", group.index, group.id);
+        }
+        return Ok(false);
+    };
+
+    let group = pe_icon(pe_path, icon_index)?;
+    let output_path = match output_path {
+        Some(output_path) => output_path,
+        None => {
+            let stem = pe_path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .with_context(|| anyhow!("Failed to get file name: {pe_path:?}"))?;
+            PathBuf::from(format!("{stem}-{icon_index}.ico"))
+        }
+    };
+    std::fs::write(&output_path, &group.ico)?;
+    info!("{}: {output_path:?}", t!("SUCCESS_EXTRACT_ICON"));
     Ok(true)
 }
 
-pub fn change_single_shortcut_icon(link_path: &Path, icon_path: &Path) -> Result<bool> {
-    let match_icon_ext = ["ico", "png", "svg", "bmp", "webp", "tiff", "exe"];
+/// Print the frames of an .ico file, `*` marks the one that is used for 256px
+pub fn list_ico_frames(icon_path: &Path) -> Result<bool> {
+    let icon = IcoFile::open(icon_path)?;
+    let best = icon.best_frame(256);
+    for (index, frame) in icon.frames.iter().enumerate() {
+        let mark = if std::ptr::eq(frame, best) { "*" } else { " " };
+        println!("{mark} {index:>2}  {frame}");
+    }
+    Ok(false)
+}
+
+/// List the ICO profiles with the current one marked, or switch to `profile`
+pub fn set_ico_profile(profile: Option<&str>) -> Result<bool> {
+    let Some(profile) = profile else {
+        let current = IcoProfile::load()?;
+        for profile in IcoProfile::ALL {
+            let mark = if profile == current { "*" } else { " " };
+            let frames = profile
+                .frames()
+                .iter()
+                .map(|frame| format!("{}{:?}", frame.size, frame.encoding))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!("{mark} {:<10}  {frames}", profile.name());
+        }
+        return Ok(false);
+    };
+
+    let profile = profile.parse::<IcoProfile>()?;
+    profile.save()?;
+    info!("{}: {profile}", t!("SUCCESS_SET_ICO_PROFILE"));
+    Ok(false)
+}
+
+/// `icon_index` picks the icon group of a program or library, a negative index is a resource id.
+/// Other icon files only have index 0.
+pub fn change_single_shortcut_icon(
+    link_path: &Path,
+    icon_path: &Path,
+    icon_index: i32,
+) -> Result<bool> {
+    let match_icon_ext = ["ico", "png", "svg", "bmp", "webp", "tiff"];
 
     let _is_icon = icon_path
         .extension()
         .and_then(OsStr::to_str)
         .map(str::to_lowercase)
-        .filter(|ext| match_icon_ext.contains(&ext.as_str()))
+        .filter(|ext| match_icon_ext.contains(&ext.as_str()) || PE_ICON_EXT.contains(&ext.as_str()))
         .with_context(|| anyhow!("the file is not an icon: {icon_path:?}"))?;
+    ensure!(
+        icon_index == 0 || is_pe_icon_file(icon_path),
+        "only programs and libraries have icon indexes: {icon_path:?},{icon_index}"
+    );
 
-    let (shell_link, persist_file) = initialize_com_and_create_shell_link()?;
-    let link_prop = ManageLinkProp::get_info(link_path, &shell_link, &persist_file)?;
+    let link_prop = backend_for(link_path)?.read(link_path)?;
     let link_path = &link_prop.path;
     let link_target_path = &link_prop.target_path;
     let link_icon_path = &link_prop.icon_path;
@@ -113,18 +192,31 @@ pub fn change_single_shortcut_icon(link_path: &Path, icon_path: &Path) -> Result
     {
         let select_icon_path = icon_path.to_string_lossy().to_lowercase();
         let link_icon_path = link_icon_path.to_lowercase();
-        if select_icon_path == link_icon_path || &select_icon_path == link_target_path {
+        let same_index = link_prop.icon_index == icon_index.to_string();
+        if same_index
+            && (select_icon_path == link_icon_path || &select_icon_path == link_target_path)
+        {
             return Ok(false);
         };
     }
 
+    // 索引不存在时不写入快捷方式
+    if is_pe_icon_file(icon_path) {
+        pe_icon(icon_path, icon_index)?;
+    }
     let icon_path = process_icon(icon_path)?;
 
-    persist_file.Load(link_path, co::STGM::WRITE)?;
-    shell_link.SetIconLocation(&icon_path.to_string_lossy(), 0)?;
-    persist_file.Save(None, true)?;
-
-    info!("{}:\n{link_path}\n{icon_path:?}", t!("SHORTCUT"));
+    set_link_icon_location(
+        backends(),
+        link_path,
+        &icon_path.to_string_lossy(),
+        icon_index,
+    )?;
+
+    info!(
+        "{}:\n{link_path}\n{icon_path:?},{icon_index}",
+        t!("SHORTCUT")
+    );
 
     Ok(true)
 }
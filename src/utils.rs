@@ -1,20 +1,22 @@
+#[cfg(windows)]
 use std::env;
 use std::fs::OpenOptions;
 use std::io::{ErrorKind::AlreadyExists, Write};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use anyhow::{Context, Result};
-use log::{error, info};
+#[cfg(windows)]
+use log::error;
+use log::info;
+#[cfg(windows)]
 use rust_i18n::t;
+#[cfg(windows)]
 use tauri_winrt_notification::{IconCrop, Toast};
 
 pub const LOGO_IMAGE: &[u8] = include_bytes!("../resources/logo.png");
 
 pub fn ensure_local_app_folder_exists() -> Result<PathBuf> {
-    let local_app_data_path = env::var("LOCALAPPDATA")
-        .context("Failed to fetches the environment 'LOCALAPPDATA' variable")?;
-
-    let local_link_echo_path = Path::new(&local_app_data_path).join("LinkEcho");
+    let local_link_echo_path = local_app_data_path()?.join("LinkEcho");
 
     std::fs::create_dir_all(&local_link_echo_path)
         .context("Failed to create LinkEcho directory at ../Users/MyUser/Appdata/Local")?;
@@ -22,6 +24,20 @@ pub fn ensure_local_app_folder_exists() -> Result<PathBuf> {
     Ok(local_link_echo_path)
 }
 
+#[cfg(windows)]
+fn local_app_data_path() -> Result<PathBuf> {
+    env::var("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .context("Failed to fetches the environment 'LOCALAPPDATA' variable")
+}
+
+/// `$XDG_DATA_HOME` or `~/.local/share`
+#[cfg(not(windows))]
+fn local_app_data_path() -> Result<PathBuf> {
+    crate::link::desktop_entry::data_home()
+        .context("Failed to fetches the environment 'XDG_DATA_HOME' or 'HOME' variable")
+}
+
 pub fn ensure_logo_exists() -> Result<PathBuf> {
     let local_app_folder_path = ensure_local_app_folder_exists()?;
     let logo_path = local_app_folder_path.join("logo.png");
@@ -40,6 +56,7 @@ pub fn ensure_logo_exists() -> Result<PathBuf> {
     }
 }
 
+#[cfg(windows)]
 pub fn notify(messages: &str) {
     let logo_path = ensure_logo_exists()
         .inspect_err(|e| error!("Logo file does not exist - {e}"))
@@ -54,6 +71,7 @@ pub fn notify(messages: &str) {
         .expect("Unable to toast")
 }
 
+#[cfg(windows)]
 pub fn notify_open_folder(messages: &str, path: &str) {
     let logo_path = ensure_logo_exists()
         .inspect_err(|e| error!("Logo file does not exist - {e}"))
@@ -78,3 +96,14 @@ pub fn notify_open_folder(messages: &str, path: &str) {
         .inspect_err(|e| error!("Unable to toast: {e}"))
         .expect("Unable to toast")
 }
+
+/// There is no toast outside Windows, the message goes to the log - 非 Windows 系统仅记录日志
+#[cfg(not(windows))]
+pub fn notify(messages: &str) {
+    info!("{messages}");
+}
+
+#[cfg(not(windows))]
+pub fn notify_open_folder(messages: &str, path: &str) {
+    info!("{messages}: {path}");
+}